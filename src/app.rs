@@ -1,36 +1,80 @@
-use crate::config::AppConfig;
-use crate::file::renamer::FileRenamer;
-use crate::file::watcher::FileWatcher;
+use crate::config::{AppConfig, show_error};
+use crate::logging;
+use crate::monitor::checker::Missing;
+use crate::reload::ConfigSource;
+use crate::service::Service;
 use crate::tray::events::TrayEvent;
 use crate::tray::menu::TrayMenu;
-use crate::tray::runner::TrayRunner;
+use crate::tray::runner::{TrayRunner, UserEvent};
 use crate::tray::tray::Tray;
-use std::sync::Arc;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// Missing documents named in the tray before the rest are counted.
+const MISSING_SHOWN: usize = 3;
 
 pub struct App {
     tray_runner: TrayRunner,
-    file_watcher: FileWatcher,
-    config: Arc<AppConfig>,
+    service: Service,
 }
 
 impl App {
-    pub fn new(config: AppConfig) -> Self {
+    pub fn new(config: AppConfig, source: ConfigSource) -> Self {
         let menu = TrayMenu::default();
         let tray = Tray::new(&menu);
 
         Self {
             tray_runner: TrayRunner::new(tray, menu),
-            file_watcher: FileWatcher::new(config.clone()),
-            config: Arc::new(config),
+            service: Service::new(config, source),
         }
     }
 
     pub fn listen_files(&mut self) -> &mut Self {
-        let config = self.config.clone();
+        self.service.listen_files();
+        self
+    }
+
+    pub fn rename_files(&mut self, files: Vec<PathBuf>) -> &mut Self {
+        self.service.rename_files(files);
+        self
+    }
+
+    /// Shows the expected documents that have not arrived in the tray.
+    pub fn check_missing(&mut self) -> &mut Self {
+        let proxy = self.tray_runner.proxy();
+
+        self.service.check_missing(move |missing| {
+            if let Some(proxy) = &proxy {
+                let _ = proxy.send_event(UserEvent::Warning(summary(missing)));
+            }
+        });
+
+        self
+    }
+
+    /// Answers `autodate ctl`, keeping the tray in step when it pauses or
+    /// resumes the watcher.
+    pub fn serve_control(&mut self) -> &mut Self {
+        let proxy = self.tray_runner.proxy();
 
-        self.file_watcher.listen(move |path| {
-            if let Err(err) = FileRenamer::new((*config).clone()).rename_file(&path) {
-                log::error!("Failed to rename file {}: {}", path.display(), err);
+        self.service.serve_control(move |paused| {
+            if let Some(proxy) = &proxy {
+                let _ = proxy.send_event(UserEvent::Running(!paused));
+            }
+        });
+
+        self
+    }
+
+    /// Reloads the configuration when its files change, showing why a new
+    /// one was not taken.
+    pub fn watch_config(&mut self) -> &mut Self {
+        // The proxy can be sent to other threads but not shared on Windows
+        let proxy = Mutex::new(self.tray_runner.proxy());
+
+        self.service.watch_config(move |err| {
+            if let Some(proxy) = &*proxy.lock().unwrap() {
+                let _ = proxy.send_event(UserEvent::ConfigError(err));
             }
         });
 
@@ -38,14 +82,17 @@ impl App {
     }
 
     pub fn run_tray(&mut self) -> &mut Self {
-        let config = self.config.clone();
-        let file_handler = self.file_watcher.get_handler();
+        let config = self.service.config();
+        let file_handler = self.service.handler();
+        let reloader = self.service.reloader();
 
         self.tray_runner.run(move |event, tray_ref| match event {
             TrayEvent::Title => {
                 log::info!("Opening monitored folder");
 
-                if let Err(e) = open::that(config.watch_path.clone()) {
+                let watch_path = config.read().unwrap().watch_path.clone();
+
+                if let Err(e) = open::that(watch_path) {
                     log::error!("Failed to open folder: {}", e);
                 }
             }
@@ -60,6 +107,22 @@ impl App {
                     file_handler.pause();
                 }
             }
+            TrayEvent::Warning => {}
+            TrayEvent::Reload => {
+                log::info!("Reloading configuration");
+
+                if let Err(err) = reloader.reload() {
+                    show_error(err);
+                }
+            }
+            TrayEvent::OpenLog => {
+                let path = logging::log_path();
+                log::info!("Opening log file: {}", path.display());
+
+                if let Err(e) = open::that(&path) {
+                    log::error!("Failed to open log file: {}", e);
+                }
+            }
             TrayEvent::Exit => {
                 log::info!("Application exiting");
             }
@@ -68,3 +131,21 @@ impl App {
         self
     }
 }
+
+fn summary(missing: &[Missing]) -> Option<String> {
+    if missing.is_empty() {
+        return None;
+    }
+
+    let mut names: Vec<String> = missing
+        .iter()
+        .take(MISSING_SHOWN)
+        .map(|entry| format!("{} {}", entry.name, entry.period))
+        .collect();
+
+    if missing.len() > MISSING_SHOWN {
+        names.push(format!("{} more", missing.len() - MISSING_SHOWN));
+    }
+
+    Some(format!("Missing: {}", names.join(", ")))
+}
@@ -0,0 +1,192 @@
+use crate::lock_file;
+use crate::rotation::rotate;
+use crate::source::DateSource;
+use crate::state::Outcome;
+use chrono::{DateTime, FixedOffset, NaiveDate, TimeDelta};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+
+/// Held while writing an event of this process. The lock file next to the
+/// log does the same across processes, so one never appends to a file
+/// another is rotating.
+static WRITE_LOCK: Mutex<()> = Mutex::new(());
+
+/// One decision taken about a file.
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditEvent {
+    pub time: DateTime<FixedOffset>,
+    /// The `WATCH_PATH` the file was found in.
+    pub profile: String,
+    pub file: PathBuf,
+    pub path: Option<PathBuf>,
+    pub rule: Option<String>,
+    pub source: Option<DateSource>,
+    pub date: Option<NaiveDate>,
+    /// Counter added because the name was already taken.
+    pub collision: Option<usize>,
+    pub outcome: Outcome,
+    pub error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Started {
+    time: DateTime<FixedOffset>,
+}
+
+/// Log of every decision in `AUDIT_LOG`, one JSON object per line. The file
+/// is rotated to `<name>.1.jsonl`, `<name>.2.jsonl`... when it grows past
+/// `max_size` bytes or its first event is older than `max_age`, keeping at
+/// most `keep` rotated files.
+pub struct AuditLog {
+    path: PathBuf,
+    max_size: u64,
+    max_age: TimeDelta,
+    keep: usize,
+}
+
+impl AuditLog {
+    pub fn new(path: &Path, max_size: u64, max_age: TimeDelta, keep: usize) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            max_size,
+            max_age,
+            keep,
+        }
+    }
+
+    pub fn write(&self, event: &AuditEvent) -> io::Result<()> {
+        let _guard = WRITE_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+        let _lock = lock_file::lock(&self.path)?;
+
+        self.rotate_if_due(event.time)?;
+
+        let mut line = serde_json::to_string(event).map_err(io::Error::other)?;
+        line.push('\n');
+
+        let mut file = OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(&self.path)?;
+
+        file.write_all(line.as_bytes())
+    }
+
+    fn rotate_if_due(&self, now: DateTime<FixedOffset>) -> io::Result<()> {
+        let Ok(metadata) = fs::metadata(&self.path) else {
+            return Ok(());
+        };
+
+        let too_big = metadata.len() >= self.max_size;
+        let too_old = self
+            .started()
+            .is_some_and(|started| now - started > self.max_age);
+
+        if !too_big && !too_old {
+            return Ok(());
+        }
+
+        rotate(&self.path, self.keep)
+    }
+
+    /// Time of the first event in the current file.
+    fn started(&self) -> Option<DateTime<FixedOffset>> {
+        let mut line = String::new();
+        BufReader::new(File::open(&self.path).ok()?)
+            .read_line(&mut line)
+            .ok()?;
+
+        serde_json::from_str::<Started>(&line)
+            .ok()
+            .map(|started| started.time)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rotation::rotated_path;
+    use std::env;
+    use std::sync::Arc;
+    use std::thread;
+
+    fn log_path(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("autodate-audit-{}", name));
+        let _ = fs::remove_dir_all(&dir);
+        dir.join("audit.jsonl")
+    }
+
+    fn event(time: &str) -> AuditEvent {
+        AuditEvent {
+            time: DateTime::parse_from_rfc3339(time).unwrap(),
+            profile: "/watch".to_string(),
+            file: PathBuf::from("/watch/scan.pdf"),
+            path: Some(PathBuf::from("/watch/2024-01-15.pdf")),
+            rule: None,
+            source: None,
+            date: NaiveDate::from_ymd_opt(2024, 1, 15),
+            collision: None,
+            outcome: Outcome::Renamed,
+            error: None,
+        }
+    }
+
+    fn lines(path: &Path) -> usize {
+        fs::read_to_string(path).map_or(0, |content| content.lines().count())
+    }
+
+    #[test]
+    fn rotates_when_too_big() {
+        let path = log_path("size");
+        let log = AuditLog::new(&path, 1, TimeDelta::days(30), 2);
+
+        for _ in 0..4 {
+            log.write(&event("2024-01-15T10:00:00+01:00")).unwrap();
+        }
+
+        assert_eq!(lines(&path), 1);
+        assert_eq!(lines(&rotated_path(&path, 1)), 1);
+        assert_eq!(lines(&rotated_path(&path, 2)), 1);
+        assert!(!rotated_path(&path, 3).exists());
+    }
+
+    #[test]
+    fn rotates_when_too_old() {
+        let path = log_path("age");
+        let log = AuditLog::new(&path, u64::MAX, TimeDelta::days(1), 1);
+
+        log.write(&event("2024-01-15T10:00:00+01:00")).unwrap();
+        log.write(&event("2024-01-16T09:00:00+01:00")).unwrap();
+        assert_eq!(lines(&path), 2);
+
+        log.write(&event("2024-01-16T11:00:00+01:00")).unwrap();
+        assert_eq!(lines(&path), 1);
+        assert_eq!(lines(&rotated_path(&path, 1)), 2);
+    }
+
+    #[test]
+    fn keeps_every_event_written_concurrently() {
+        let path = log_path("concurrent");
+        let log = Arc::new(AuditLog::new(&path, 2048, TimeDelta::days(30), 100));
+
+        let writers: Vec<_> = (0..4)
+            .map(|_| {
+                let log = Arc::clone(&log);
+                thread::spawn(move || {
+                    for _ in 0..25 {
+                        log.write(&event("2024-01-15T10:00:00+01:00")).unwrap();
+                    }
+                })
+            })
+            .collect();
+
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        let rotated: usize = (1..=100).map(|n| lines(&rotated_path(&path, n))).sum();
+        assert_eq!(lines(&path) + rotated, 100);
+    }
+}
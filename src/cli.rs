@@ -0,0 +1,373 @@
+use crate::config::AppConfig;
+use crate::control::client;
+use crate::control::protocol::Request;
+use crate::instance::{InstanceError, InstanceLock};
+use crate::logging::LogConfig;
+use crate::monitor::checker::find_missing;
+use crate::reload::ConfigSource;
+use crate::service::Service;
+use crate::state::StateStore;
+use clap::{Parser, Subcommand};
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{self, Path, PathBuf};
+use std::process::ExitCode;
+use std::thread;
+use std::time::Duration;
+
+/// Exit code of `missing` when documents are missing.
+const EXIT_MISSING: u8 = 2;
+
+/// How often `logs --follow` checks the log file for new lines.
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Renames the files dropped into a folder after their date. Without a
+/// command, runs in the system tray.
+#[derive(Parser)]
+#[command(version, about, args_conflicts_with_subcommands = true)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    /// Files to rename right away, by the instance already running if any
+    pub files: Vec<PathBuf>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// List the expected documents that have not arrived
+    Missing,
+    /// Inspect or maintain the record of processed files
+    State {
+        #[command(subcommand)]
+        command: StateCommand,
+    },
+    /// Run without a tray icon, controlled with `ctl`
+    Headless,
+    /// Send a command to the running instance
+    Ctl {
+        #[command(subcommand)]
+        command: CtlCommand,
+    },
+    /// Print the end of the log file
+    Logs {
+        /// Keep printing lines as they are written
+        #[arg(short, long)]
+        follow: bool,
+        /// Number of lines to print
+        #[arg(short = 'n', long, default_value_t = 50)]
+        lines: usize,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum CtlCommand {
+    /// Show whether new files are being renamed
+    Status,
+    /// Stop renaming new files
+    Pause,
+    /// Start renaming new files again
+    Resume,
+    /// Rename the files already in the folder that are not named yet
+    Rescan,
+    /// Rename a file right away
+    RenameNow { path: PathBuf },
+    /// Read the configuration again
+    ReloadConfig,
+    /// Open the monitored folder
+    OpenFolder,
+    /// Show the latest processed files
+    Recent {
+        /// Number of files to show
+        #[arg(short = 'n', long)]
+        count: Option<usize>,
+    },
+}
+
+impl CtlCommand {
+    fn request(self) -> io::Result<Request> {
+        Ok(match self {
+            CtlCommand::Status => Request::Status,
+            CtlCommand::Pause => Request::Pause,
+            CtlCommand::Resume => Request::Resume,
+            CtlCommand::Rescan => Request::Rescan,
+            // The running instance may have another working directory
+            CtlCommand::RenameNow { path } => Request::RenameNow {
+                path: path::absolute(path)?,
+            },
+            CtlCommand::ReloadConfig => Request::ReloadConfig,
+            CtlCommand::OpenFolder => Request::OpenFolder,
+            CtlCommand::Recent { count } => Request::Recent { count },
+        })
+    }
+}
+
+#[derive(Subcommand)]
+pub enum StateCommand {
+    /// Print every processed file as a JSON array
+    Export,
+    /// Drop superseded and unreadable records
+    Compact,
+}
+
+impl Command {
+    /// `source` is where the configuration is read from, kept so `headless`
+    /// can reload it.
+    pub fn run(self, source: ConfigSource) -> ExitCode {
+        // Logs are read without the rest of the configuration, which may be
+        // what is broken
+        if let Command::Logs { follow, lines } = self {
+            return logs(&LogConfig::load().path(), follow, lines);
+        }
+
+        let config = match AppConfig::load() {
+            Ok(config) => config,
+            Err(err) => {
+                eprintln!("{}", err);
+                return ExitCode::FAILURE;
+            }
+        };
+
+        match self {
+            Command::Missing => missing(&config),
+            Command::State { command } => state(&config, command),
+            Command::Headless => headless(config, source),
+            Command::Ctl { command } => ctl(&config, command),
+            Command::Logs { .. } => unreachable!("Handled before loading the configuration"),
+        }
+    }
+}
+
+fn state(config: &AppConfig, command: StateCommand) -> ExitCode {
+    let store = StateStore::new(&config.state_path());
+
+    let result = match command {
+        StateCommand::Export => store.records().map(|records| {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&records).unwrap_or_default()
+            );
+        }),
+        StateCommand::Compact => store.compact().map(|kept| {
+            println!("Kept {} records in {}", kept, config.state_path().display());
+        }),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn headless(config: AppConfig, source: ConfigSource) -> ExitCode {
+    let _lock = match InstanceLock::acquire(&config) {
+        Ok(lock) => Some(lock),
+        Err(InstanceError::Running(pid)) => {
+            eprintln!("{}", already_running(&config, pid));
+            return ExitCode::FAILURE;
+        }
+        Err(err) => {
+            log::warn!("{}, starting without a lock", err);
+            None
+        }
+    };
+
+    log::info!("Initializing application without tray");
+
+    Service::new(config, source)
+        .listen_files()
+        .check_missing(|_| {})
+        .serve_control(|_| {})
+        .watch_config(|_| {});
+
+    loop {
+        thread::park();
+    }
+}
+
+fn ctl(config: &AppConfig, command: CtlCommand) -> ExitCode {
+    match command.request() {
+        Ok(request) if send(config, &request) => ExitCode::SUCCESS,
+        Ok(_) => ExitCode::FAILURE,
+        Err(err) => {
+            eprintln!("{}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn already_running(config: &AppConfig, pid: Option<u32>) -> String {
+    match pid {
+        Some(pid) => format!(
+            "Autodate is already running for {} (PID {})",
+            config.watch_path, pid
+        ),
+        None => format!("Autodate is already running for {}", config.watch_path),
+    }
+}
+
+/// Hands what a second launch was asked to do to the instance already
+/// running: renaming the files given, or else opening the folder. Its
+/// status is printed first.
+pub fn forward(config: &AppConfig, pid: Option<u32>, files: &[PathBuf]) -> ExitCode {
+    println!("{}", already_running(config, pid));
+
+    let requests: io::Result<Vec<Request>> = if files.is_empty() {
+        Ok(vec![Request::OpenFolder])
+    } else {
+        files
+            .iter()
+            .map(|file| {
+                Ok(Request::RenameNow {
+                    path: path::absolute(file)?,
+                })
+            })
+            .collect()
+    };
+
+    let requests = match requests {
+        Ok(requests) => requests,
+        Err(err) => {
+            eprintln!("{}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut succeeded = send(config, &Request::Status);
+
+    for request in &requests {
+        succeeded &= send(config, request);
+    }
+
+    if succeeded {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+/// Sends `request` to the running instance and prints its answer. Returns
+/// whether it succeeded.
+fn send(config: &AppConfig, request: &Request) -> bool {
+    match client::send(config, request) {
+        Ok(response) if response.ok => {
+            if let Some(data) = response.data {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&data).unwrap_or_default()
+                );
+            }
+            true
+        }
+        Ok(response) => {
+            eprintln!("{}", response.error.unwrap_or_default());
+            false
+        }
+        Err(err) => {
+            eprintln!("No instance is running for {} ({})", config.watch_path, err);
+            false
+        }
+    }
+}
+
+fn missing(config: &AppConfig) -> ExitCode {
+    if config.expectations.is_empty() {
+        println!("No expectations configured, set EXPECTATIONS_FILE");
+        return ExitCode::SUCCESS;
+    }
+
+    match find_missing(config, config.timezone.now().date_naive()) {
+        Ok(missing) if missing.is_empty() => {
+            println!("No missing documents");
+            ExitCode::SUCCESS
+        }
+        Ok(missing) => {
+            for entry in &missing {
+                println!("{}: {} (due {})", entry.name, entry.period, entry.due);
+            }
+            ExitCode::from(EXIT_MISSING)
+        }
+        Err(err) => {
+            eprintln!("Invalid date format: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn logs(path: &Path, follow: bool, lines: usize) -> ExitCode {
+    let content = match fs::read(path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == io::ErrorKind::NotFound && follow => Vec::new(),
+        Err(err) => {
+            eprintln!("Failed to read log file {}: {}", path.display(), err);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let text = String::from_utf8_lossy(&content);
+    let skip = text.lines().count().saturating_sub(lines);
+
+    for line in text.lines().skip(skip) {
+        println!("{}", line);
+    }
+
+    if !follow {
+        return ExitCode::SUCCESS;
+    }
+
+    let mut position = content.len() as u64;
+
+    loop {
+        thread::sleep(FOLLOW_INTERVAL);
+
+        match print_new(path, position) {
+            Ok(next) => position = next,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => position = 0,
+            Err(err) => {
+                eprintln!("Failed to read log file {}: {}", path.display(), err);
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+}
+
+/// Prints what was written to the log after `position` and returns the new
+/// end. A file shorter than `position` was rotated, so it is read from the
+/// start.
+fn print_new(path: &Path, position: u64) -> io::Result<u64> {
+    let mut file = File::open(path)?;
+    let length = file.metadata()?.len();
+
+    let position = if length < position { 0 } else { position };
+
+    if length == position {
+        return Ok(position);
+    }
+
+    let mut content = Vec::new();
+    file.seek(SeekFrom::Start(position))?;
+    file.read_to_end(&mut content)?;
+
+    let mut stdout = io::stdout();
+    stdout.write_all(&content)?;
+    stdout.flush()?;
+
+    Ok(position + content.len() as u64)
+}
+
+/// Release builds have no console of their own, so command output goes to
+/// the console they were started from.
+#[cfg(windows)]
+pub fn attach_console() {
+    use windows_sys::Win32::System::Console::{ATTACH_PARENT_PROCESS, AttachConsole};
+
+    // Fails when there is no parent console, which leaves nothing to print to
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+pub fn attach_console() {}
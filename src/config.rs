@@ -1,7 +1,48 @@
+use crate::date::formatter::{DateFormatter, NameCase};
+use crate::date::matcher::NameMatcher;
+use crate::date::timezone::Timezone;
+use crate::file::extension::ExtensionSet;
+use crate::file::rules::Rules;
+use crate::file::sanitizer::Sanitizer;
+use crate::file::sniffer::FileType;
+use crate::monitor::expectations::Expectations;
+use crate::source::DateSource;
+use crate::source::vendor::Vendors;
+use crate::toml_file;
+use chrono::{Locale, NaiveDate};
 use native_dialog::{MessageDialogBuilder, MessageLevel};
-use serde::Deserialize;
+use regex::Regex;
+use serde::{Deserialize, Deserializer};
+use sha2::{Digest, Sha256};
+use std::env;
+use std::fs;
+use std::path::{self, Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
 use thiserror::Error;
 
+const DEFAULT_QUARANTINE_DIR: &str = "quarantine";
+
+const DEFAULT_SEQUENCE_FILE: &str = "sequences.json";
+
+const DEFAULT_STATE_FILE: &str = "state.jsonl";
+
+const DEFAULT_AUDIT_LOG: &str = "audit.jsonl";
+
+const DEFAULT_PDF_DATE_PATTERNS: &[&str] = &[
+    r"(?i)fecha\s+(?:de\s+)?(?:emisi[oó]n|expedici[oó]n|factura)\s*:?\s*(?P<day>\d{1,2})[/.-](?P<month>\d{1,2})[/.-](?P<year>\d{4})",
+    r"(?i)fecha\s+(?:de\s+)?(?:emisi[oó]n|expedici[oó]n|factura)\s*:?\s*(?P<year>\d{4})-(?P<month>\d{2})-(?P<day>\d{2})",
+    r"(?i)invoice\s+date\s*:?\s*(?P<year>\d{4})-(?P<month>\d{2})-(?P<day>\d{2})",
+];
+
+const DEFAULT_FILENAME_DATE_PATTERNS: &[&str] = &[
+    r"(?:^|\D)(?P<year>(?:19|20)\d{2})[-_.]?(?P<month>0[1-9]|1[0-2])[-_.]?(?P<day>0[1-9]|[12]\d|3[01])(?:\D|$)",
+    r"(?:^|\D)(?P<day>0[1-9]|[12]\d|3[01])[-_.](?P<month>0[1-9]|1[0-2])[-_.](?P<year>(?:19|20)\d{2})(?:\D|$)",
+];
+
+/// Configuration shared between threads, replaced as a whole on reload.
+pub type SharedConfig = Arc<RwLock<AppConfig>>;
+
 #[derive(Error, Clone, Debug, Deserialize)]
 pub enum AppConfigError {
     #[error("Failed to load configuration {0:?}")]
@@ -12,22 +53,375 @@ pub enum AppConfigError {
 pub struct AppConfig {
     pub watch_path: String,
     pub file_format: String,
-    pub date_validation: String,
+    pub date_validation: Option<String>,
     pub delay_seconds: u64,
+    #[serde(default = "default_compound_extensions")]
+    pub compound_extensions: Vec<String>,
+    #[serde(default)]
+    pub infer_extension: bool,
+    #[serde(default)]
+    pub allowed_types: Vec<FileType>,
+    pub quarantine_path: Option<String>,
+    #[serde(default = "default_name_replacement")]
+    pub name_replacement: String,
+    #[serde(default = "default_max_name_length")]
+    pub max_name_length: usize,
+    #[serde(default, deserialize_with = "deserialize_locale")]
+    pub locale: Option<Locale>,
+    #[serde(default)]
+    pub name_case: NameCase,
+    #[serde(default)]
+    pub timezone: Timezone,
+    pub period_grace_days: Option<u32>,
+    #[serde(default)]
+    pub period_offset_months: i32,
+    #[serde(default)]
+    pub period_business_days: bool,
+    #[serde(default)]
+    pub holidays: Vec<NaiveDate>,
+    #[serde(
+        default = "default_fiscal_year_start",
+        deserialize_with = "deserialize_month"
+    )]
+    pub fiscal_year_start: u32,
+    #[serde(default = "default_date_sources")]
+    pub date_sources: Vec<DateSource>,
+    #[serde(default)]
+    pub unpack_archives: bool,
+    #[serde(default)]
+    pub keep_archives: bool,
+    #[serde(default)]
+    pub extract_attachments: bool,
+    #[serde(default = "default_max_archive_entries")]
+    pub max_archive_entries: usize,
+    #[serde(default = "default_max_unpacked_size")]
+    pub max_unpacked_size: u64,
+    #[serde(default)]
+    pub group_extensions: Vec<ExtensionSet>,
+    #[serde(default = "default_group_window_seconds")]
+    pub group_window_seconds: u64,
+    #[serde(
+        default = "default_pdf_date_patterns",
+        deserialize_with = "deserialize_patterns"
+    )]
+    pub pdf_date_patterns: Vec<Regex>,
+    #[serde(
+        default = "default_filename_date_patterns",
+        deserialize_with = "deserialize_patterns"
+    )]
+    pub filename_date_patterns: Vec<Regex>,
+    #[serde(default)]
+    pub reject_future_dates: bool,
+    pub max_date_age_months: Option<u32>,
+    pub rules_file: Option<String>,
+    #[serde(skip)]
+    pub rules: Rules,
+    pub vendors_file: Option<String>,
+    #[serde(skip)]
+    pub vendors: Vendors,
+    #[serde(default)]
+    pub vendor_folders: bool,
+    pub expectations_file: Option<String>,
+    #[serde(skip)]
+    pub expectations: Expectations,
+    pub sequence_file: Option<String>,
+    #[serde(default = "default_sequence_digits")]
+    pub sequence_digits: usize,
+    pub state_file: Option<String>,
+    pub audit_log: Option<String>,
+    #[serde(default = "default_audit_max_size_mb")]
+    pub audit_max_size_mb: u64,
+    #[serde(default = "default_audit_max_age_days")]
+    pub audit_max_age_days: u32,
+    #[serde(default = "default_audit_keep_files")]
+    pub audit_keep_files: usize,
 }
 
 impl AppConfig {
     pub fn new() -> AppConfig {
-        envy::from_env::<AppConfig>()
-            .map_err(|err| AppConfigError::LoadError(err.to_string()))
-            .unwrap_or_else(|err| {
-                show_error(err);
-                std::process::exit(1);
+        Self::load().unwrap_or_else(|err| {
+            show_error(err);
+            std::process::exit(1);
+        })
+    }
+
+    /// Reads the configuration from the environment, along with the files
+    /// it points to.
+    pub fn load() -> Result<AppConfig, AppConfigError> {
+        Self::load_from(env::vars())
+    }
+
+    /// Reads the configuration from `vars` instead of the environment.
+    pub fn load_from(
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<AppConfig, AppConfigError> {
+        let mut config = envy::from_iter::<_, AppConfig>(vars)
+            .map_err(|err| AppConfigError::LoadError(err.to_string()))?;
+
+        if let Some(path) = &config.rules_file {
+            config.rules = Rules::load(Path::new(path))
+                .map_err(|err| AppConfigError::LoadError(err.to_string()))?;
+        }
+
+        if let Some(path) = &config.vendors_file {
+            config.vendors = Vendors::load(Path::new(path))
+                .map_err(|err| AppConfigError::LoadError(err.to_string()))?;
+        }
+
+        if let Some(path) = &config.expectations_file {
+            config.expectations = Expectations::load(Path::new(path))
+                .map_err(|err| AppConfigError::LoadError(err.to_string()))?;
+        }
+
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Checks the formats before chrono is given them, as it panics on
+    /// specifiers it doesn't know, and compiles the patterns renamed files
+    /// are recognised by.
+    fn validate(&self) -> Result<(), AppConfigError> {
+        check_format("FILE_FORMAT", &self.file_format)?;
+
+        for rule in self.rules.iter() {
+            let name = rule.name.as_deref().unwrap_or("without a name");
+
+            if let Some(format) = &rule.format {
+                check_format(&format!("format of rule {}", name), format)?;
+            }
+
+            if let Some(destination) = &rule.destination {
+                check_format(&format!("destination of rule {}", name), destination)?;
+            }
+        }
+
+        if !Sanitizer::is_allowed(&self.name_replacement) {
+            return Err(AppConfigError::LoadError(format!(
+                "Invalid NAME_REPLACEMENT `{}`: it can't contain < > : \" / \\ | ? * or control characters",
+                self.name_replacement.escape_default()
+            )));
+        }
+
+        NameMatcher::for_config(self).map_err(|err| match &self.date_validation {
+            Some(pattern) => {
+                AppConfigError::LoadError(format!("Invalid DATE_VALIDATION `{}`: {}", pattern, err))
+            }
+            None => AppConfigError::LoadError(err.to_string()),
+        })?;
+
+        Ok(())
+    }
+
+    /// Whether `source` is used by `DATE_SOURCES` or any rule.
+    pub fn uses_source(&self, source: DateSource) -> bool {
+        self.date_sources.contains(&source)
+            || self.rules.iter().any(|rule| {
+                rule.date_sources
+                    .as_ref()
+                    .is_some_and(|sources| sources.contains(&source))
             })
     }
+
+    pub fn quarantine_dir(&self) -> PathBuf {
+        match &self.quarantine_path {
+            Some(path) => PathBuf::from(path),
+            None => Path::new(&self.watch_path).join(DEFAULT_QUARANTINE_DIR),
+        }
+    }
+
+    /// Short identifier of `WATCH_PATH`, telling apart instances started
+    /// with different configurations. Spellings of the same folder share it.
+    pub fn profile_id(&self) -> String {
+        Sha256::digest(folder_key(&self.watch_path).as_bytes())
+            .iter()
+            .take(6)
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    /// Per-user directory for the files the app keeps between runs,
+    /// falling back to the temporary directory when the system has none.
+    pub fn data_dir(&self) -> PathBuf {
+        app_data_dir().unwrap_or_else(env::temp_dir)
+    }
+
+    pub fn sequence_path(&self) -> PathBuf {
+        match &self.sequence_file {
+            Some(path) => PathBuf::from(path),
+            None => self.data_dir().join(DEFAULT_SEQUENCE_FILE),
+        }
+    }
+
+    pub fn state_path(&self) -> PathBuf {
+        match &self.state_file {
+            Some(path) => PathBuf::from(path),
+            None => self.data_dir().join(DEFAULT_STATE_FILE),
+        }
+    }
+
+    pub fn audit_path(&self) -> PathBuf {
+        match &self.audit_log {
+            Some(path) => PathBuf::from(path),
+            None => self.data_dir().join(DEFAULT_AUDIT_LOG),
+        }
+    }
+
+    /// The rules, vendors and expectations files in use.
+    pub fn files(&self) -> Vec<PathBuf> {
+        [
+            &self.rules_file,
+            &self.vendors_file,
+            &self.expectations_file,
+        ]
+        .into_iter()
+        .flatten()
+        .map(PathBuf::from)
+        .collect()
+    }
+}
+
+fn check_format(name: &str, format: &str) -> Result<(), AppConfigError> {
+    if DateFormatter::is_valid_format(format) {
+        Ok(())
+    } else {
+        Err(AppConfigError::LoadError(format!(
+            "Invalid {} `{}`: unknown date specifier (write %% for a literal %)",
+            name, format
+        )))
+    }
 }
 
-fn show_error(error: AppConfigError) {
+/// The folder at `watch_path` as one spelling: resolved when it exists,
+/// without trailing separators, and in lowercase where paths ignore case.
+fn folder_key(watch_path: &str) -> String {
+    let path = fs::canonicalize(watch_path)
+        .or_else(|_| path::absolute(watch_path))
+        .unwrap_or_else(|_| PathBuf::from(watch_path));
+
+    let key = path.to_string_lossy();
+    let key = key.strip_prefix(r"\\?\").unwrap_or(&key);
+    let key = key.trim_end_matches(['/', '\\']);
+
+    if cfg!(windows) {
+        key.to_lowercase()
+    } else {
+        key.to_string()
+    }
+}
+
+/// The app's folder in the per-user data directory, such as
+/// `%LOCALAPPDATA%\autodate` or `~/.local/share/autodate`.
+pub fn app_data_dir() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|dir| dir.join(env!("CARGO_PKG_NAME")))
+}
+
+fn default_compound_extensions() -> Vec<String> {
+    ["tar.gz", "tar.bz2", "tar.xz", "tar.zst"]
+        .map(String::from)
+        .to_vec()
+}
+
+fn default_name_replacement() -> String {
+    "_".to_string()
+}
+
+fn default_max_name_length() -> usize {
+    255
+}
+
+fn default_fiscal_year_start() -> u32 {
+    1
+}
+
+fn default_date_sources() -> Vec<DateSource> {
+    vec![DateSource::Now]
+}
+
+fn default_max_archive_entries() -> usize {
+    100
+}
+
+fn default_max_unpacked_size() -> u64 {
+    100 * 1024 * 1024
+}
+
+fn default_group_window_seconds() -> u64 {
+    60
+}
+
+fn default_sequence_digits() -> usize {
+    3
+}
+
+fn default_audit_max_size_mb() -> u64 {
+    10
+}
+
+fn default_audit_max_age_days() -> u32 {
+    30
+}
+
+fn default_audit_keep_files() -> usize {
+    5
+}
+
+fn default_pdf_date_patterns() -> Vec<Regex> {
+    compile_patterns(DEFAULT_PDF_DATE_PATTERNS)
+}
+
+fn default_filename_date_patterns() -> Vec<Regex> {
+    compile_patterns(DEFAULT_FILENAME_DATE_PATTERNS)
+}
+
+fn compile_patterns(patterns: &[&str]) -> Vec<Regex> {
+    patterns
+        .iter()
+        .map(|pattern| Regex::new(pattern).expect("Valid default date pattern"))
+        .collect()
+}
+
+fn deserialize_month<'de, D>(deserializer: D) -> Result<u32, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let month = u32::deserialize(deserializer)?;
+
+    if (1..=12).contains(&month) {
+        Ok(month)
+    } else {
+        Err(serde::de::Error::custom(format!(
+            "month must be between 1 and 12, got {}",
+            month
+        )))
+    }
+}
+
+/// Patterns are separated by `;` since regexes often contain commas.
+fn deserialize_patterns<'de, D>(deserializer: D) -> Result<Vec<Regex>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    String::deserialize(deserializer)?
+        .split(';')
+        .map(str::trim)
+        .filter(|pattern| !pattern.is_empty())
+        .map(toml_file::compile_pattern)
+        .collect()
+}
+
+fn deserialize_locale<'de, D>(deserializer: D) -> Result<Option<Locale>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<String>::deserialize(deserializer)?
+        .map(|locale| {
+            Locale::from_str(&locale)
+                .map_err(|_| serde::de::Error::custom(format!("unknown locale `{}`", locale)))
+        })
+        .transpose()
+}
+
+pub fn show_error(error: AppConfigError) {
     MessageDialogBuilder::default()
         .set_level(MessageLevel::Error)
         .set_title("Error")
@@ -36,3 +430,97 @@ fn show_error(error: AppConfigError) {
         .show()
         .unwrap();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn load(vars: &[(&str, &str)]) -> Result<AppConfig, AppConfigError> {
+        let defaults = [
+            ("WATCH_PATH", "/watch"),
+            ("FILE_FORMAT", "%Y-%m-%d"),
+            ("DELAY_SECONDS", "0"),
+        ];
+
+        // Later values replace the defaults, as envy rejects duplicates
+        let vars: HashMap<String, String> = defaults
+            .iter()
+            .chain(vars)
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+
+        AppConfig::load_from(vars)
+    }
+
+    #[test]
+    fn loads_valid_formats() {
+        assert!(load(&[]).is_ok());
+        assert!(load(&[("FILE_FORMAT", "100%% paid %Y {vendor} {quarter}")]).is_ok());
+        assert!(load(&[("DATE_VALIDATION", r"^\d{4}-\d{2}")]).is_ok());
+    }
+
+    #[test]
+    fn rejects_unknown_specifiers() {
+        assert!(load(&[("FILE_FORMAT", "100% paid %Y")]).is_err());
+        assert!(load(&[("FILE_FORMAT", "%Y-%m-%")]).is_err());
+        assert!(load(&[("FILE_FORMAT", "%Q")]).is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_date_validation() {
+        let err = load(&[("DATE_VALIDATION", "^(\\d{4}")]).unwrap_err();
+        assert!(err.to_string().contains("DATE_VALIDATION"));
+    }
+
+    #[test]
+    fn rejects_invalid_rule_formats() {
+        let dir = env::temp_dir().join("autodate-config-tests");
+        fs::create_dir_all(&dir).unwrap();
+        let rules = dir.join("rules.toml");
+
+        for rule in [
+            "name = \"a\"\nformat = \"50% %Y\"",
+            "name = \"b\"\ndestination = \"%Y/%\"",
+        ] {
+            fs::write(&rules, format!("[[rule]]\n{}\n", rule)).unwrap();
+            let rules = rules.to_string_lossy();
+            assert!(load(&[("RULES_FILE", rules.as_ref())]).is_err(), "{}", rule);
+        }
+
+        fs::write(
+            &rules,
+            "[[rule]]\nformat = \"%Y\"\ndestination = \"ACME/%Y\"\n",
+        )
+        .unwrap();
+        let rules = rules.to_string_lossy();
+        assert!(load(&[("RULES_FILE", rules.as_ref())]).is_ok());
+    }
+
+    #[test]
+    fn shares_the_profile_between_spellings() {
+        let dir = env::temp_dir().join("autodate-config-profile");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.to_string_lossy();
+        let slashed = format!("{}{}", path, std::path::MAIN_SEPARATOR);
+
+        let profile = load(&[("WATCH_PATH", path.as_ref())]).unwrap().profile_id();
+        let other = load(&[("WATCH_PATH", slashed.as_str())])
+            .unwrap()
+            .profile_id();
+        assert_eq!(profile, other);
+
+        let elsewhere = load(&[("WATCH_PATH", "/elsewhere")]).unwrap().profile_id();
+        assert_ne!(profile, elsewhere);
+    }
+
+    #[test]
+    fn rejects_unsafe_name_replacements() {
+        assert!(load(&[("NAME_REPLACEMENT", "-")]).is_ok());
+        assert!(load(&[("NAME_REPLACEMENT", "")]).is_ok());
+
+        let err = load(&[("NAME_REPLACEMENT", "/")]).unwrap_err();
+        assert!(err.to_string().contains("NAME_REPLACEMENT"));
+        assert!(load(&[("NAME_REPLACEMENT", "\t")]).is_err());
+    }
+}
@@ -0,0 +1,21 @@
+use crate::config::AppConfig;
+use crate::control::endpoint;
+use crate::control::protocol::{Request, Response};
+use interprocess::local_socket::{Stream, prelude::*};
+use std::io::{self, BufRead, BufReader, Write};
+
+/// Sends `request` to the instance running with `config` and waits for its
+/// answer.
+pub fn send(config: &AppConfig, request: &Request) -> io::Result<Response> {
+    let stream = Stream::connect(endpoint(config)?)?;
+    let mut reader = BufReader::new(stream);
+
+    let mut line = serde_json::to_string(request).map_err(io::Error::other)?;
+    line.push('\n');
+    reader.get_mut().write_all(line.as_bytes())?;
+
+    let mut answer = String::new();
+    reader.read_line(&mut answer)?;
+
+    serde_json::from_str(&answer).map_err(io::Error::other)
+}
@@ -0,0 +1,26 @@
+pub mod client;
+pub mod protocol;
+pub mod server;
+
+use crate::config::AppConfig;
+use interprocess::local_socket::Name;
+use std::io;
+
+/// Where the instance for `config` listens: a Unix domain socket in the
+/// data directory, or a named pipe on Windows.
+#[cfg(not(windows))]
+pub fn endpoint(config: &AppConfig) -> io::Result<Name<'static>> {
+    use interprocess::local_socket::{GenericFilePath, ToFsName};
+
+    config
+        .data_dir()
+        .join(format!("autodate-{}.sock", config.profile_id()))
+        .to_fs_name::<GenericFilePath>()
+}
+
+#[cfg(windows)]
+pub fn endpoint(config: &AppConfig) -> io::Result<Name<'static>> {
+    use interprocess::local_socket::{GenericNamespaced, ToNsName};
+
+    format!("autodate-{}", config.profile_id()).to_ns_name::<GenericNamespaced>()
+}
@@ -0,0 +1,49 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::PathBuf;
+
+/// Renames returned by `recent` when no count is given.
+pub const DEFAULT_RECENT: usize = 10;
+
+/// A command sent to the running instance, as one line of JSON such as
+/// `{"command":"rename_now","path":"C:\\Invoices\\scan.pdf"}`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum Request {
+    Status,
+    Pause,
+    Resume,
+    Rescan,
+    RenameNow { path: PathBuf },
+    ReloadConfig,
+    Recent { count: Option<usize> },
+    OpenFolder,
+}
+
+/// The answer to a request, also one line of JSON.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Response {
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Response {
+    pub fn ok(data: impl Serialize) -> Self {
+        Self {
+            ok: true,
+            data: serde_json::to_value(data).ok(),
+            error: None,
+        }
+    }
+
+    pub fn error(error: impl ToString) -> Self {
+        Self {
+            ok: false,
+            data: None,
+            error: Some(error.to_string()),
+        }
+    }
+}
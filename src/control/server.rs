@@ -0,0 +1,293 @@
+use crate::config::{AppConfig, SharedConfig};
+use crate::control::endpoint;
+use crate::control::protocol::{DEFAULT_RECENT, Request, Response};
+use crate::file::handler::WatcherHandler;
+use crate::file::renamer::FileRenamer;
+use crate::monitor::checker::collect_files;
+use crate::reload::Reloader;
+use crate::state::StateStore;
+use interprocess::local_socket::{ListenerOptions, Stream, prelude::*};
+use serde::Serialize;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// Longest request read, in bytes. Requests are a line of JSON.
+const MAX_REQUEST_LENGTH: u64 = 64 * 1024;
+
+#[derive(Serialize)]
+struct Status {
+    version: &'static str,
+    pid: u32,
+    profile: String,
+    watch_path: String,
+    paused: bool,
+}
+
+#[derive(Serialize)]
+struct Rescan {
+    files: usize,
+    renamed: usize,
+    failed: usize,
+    /// Files modified within `DELAY_SECONDS`, left to the watcher.
+    recent: usize,
+}
+
+#[derive(Serialize)]
+struct Renamed {
+    path: PathBuf,
+}
+
+#[derive(Serialize)]
+struct Paused {
+    paused: bool,
+}
+
+/// Answers requests from `autodate ctl` and scripts on the local control
+/// endpoint, each connection on a thread of its own so a client that never
+/// finishes its request holds up no other.
+pub struct Controller {
+    config: SharedConfig,
+    /// The profile the app was started with, which the endpoint keeps
+    /// after a reload moves the watch.
+    profile: String,
+    reloader: Reloader,
+    handler: Arc<WatcherHandler>,
+    on_pause: Mutex<Box<dyn Fn(bool) + Send>>,
+}
+
+impl Controller {
+    /// `on_pause` is told when a request pauses or resumes the watcher, so
+    /// the tray can follow.
+    pub fn new(
+        config: SharedConfig,
+        reloader: Reloader,
+        handler: Arc<WatcherHandler>,
+        on_pause: impl Fn(bool) + Send + 'static,
+    ) -> Self {
+        let profile = config.read().unwrap().profile_id();
+
+        Self {
+            config,
+            profile,
+            reloader,
+            handler,
+            on_pause: Mutex::new(Box::new(on_pause)),
+        }
+    }
+
+    /// Starts listening on a thread of its own.
+    pub fn serve(self) -> io::Result<()> {
+        let name = endpoint(&self.config())?;
+
+        // A socket file left by an instance that crashed is replaced
+        let listener = ListenerOptions::new()
+            .name(name)
+            .try_overwrite(true)
+            .create_sync()?;
+
+        log::info!("Listening for control requests");
+
+        let controller = Arc::new(self);
+
+        thread::spawn(move || {
+            for connection in listener.incoming() {
+                let connection = match connection {
+                    Ok(connection) => connection,
+                    Err(err) => {
+                        log::warn!("Control connection failed: {}", err);
+                        continue;
+                    }
+                };
+
+                let controller = controller.clone();
+
+                thread::spawn(move || {
+                    if let Err(err) = controller.answer(connection) {
+                        log::warn!("Control connection failed: {}", err);
+                    }
+                });
+            }
+        });
+
+        Ok(())
+    }
+
+    fn answer(&self, connection: Stream) -> io::Result<()> {
+        let mut reader = BufReader::new(connection.take(MAX_REQUEST_LENGTH));
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+
+        let response = match serde_json::from_str::<Request>(&line) {
+            Ok(request) => {
+                log::info!("Control request: {:?}", request);
+                self.handle(request)
+            }
+            Err(err) => Response::error(format!("Invalid request: {}", err)),
+        };
+
+        let mut line = serde_json::to_string(&response).map_err(io::Error::other)?;
+        line.push('\n');
+        reader.get_mut().get_mut().write_all(line.as_bytes())
+    }
+
+    fn handle(&self, request: Request) -> Response {
+        match request {
+            Request::Status => Response::ok(self.status()),
+            Request::Pause => self.set_paused(true),
+            Request::Resume => self.set_paused(false),
+            Request::Rescan => Response::ok(self.rescan()),
+            Request::RenameNow { path } => self.rename_now(&path),
+            Request::ReloadConfig => self.reload_config(),
+            Request::Recent { count } => self.recent(count.unwrap_or(DEFAULT_RECENT)),
+            Request::OpenFolder => self.open_folder(),
+        }
+    }
+
+    fn config(&self) -> AppConfig {
+        self.config.read().unwrap().clone()
+    }
+
+    fn status(&self) -> Status {
+        let config = self.config();
+
+        Status {
+            version: env!("CARGO_PKG_VERSION"),
+            pid: std::process::id(),
+            profile: self.profile.clone(),
+            watch_path: config.watch_path,
+            paused: self.handler.is_paused(),
+        }
+    }
+
+    fn set_paused(&self, paused: bool) -> Response {
+        if paused {
+            log::info!("Application paused");
+            self.handler.pause();
+        } else {
+            log::info!("Application running");
+            self.handler.resume();
+        }
+
+        (self.on_pause.lock().unwrap())(paused);
+        Response::ok(Paused { paused })
+    }
+
+    /// Renames the files already in `WATCH_PATH` that are not named yet,
+    /// such as the ones dropped while the app was not running. Files
+    /// modified within `DELAY_SECONDS` may still be being written, and the
+    /// watcher renames them once they are done.
+    fn rescan(&self) -> Rescan {
+        let config = self.config();
+        let renamer = FileRenamer::new(config.clone());
+
+        let mut files = Vec::new();
+        collect_files(
+            Path::new(&config.watch_path),
+            &config.quarantine_dir(),
+            &mut files,
+        );
+
+        let mut rescan = Rescan {
+            files: files.len(),
+            renamed: 0,
+            failed: 0,
+            recent: 0,
+        };
+
+        let delay = Duration::from_secs(config.delay_seconds);
+
+        for file in &files {
+            if modified_within(file, delay) {
+                rescan.recent += 1;
+                continue;
+            }
+
+            match renamer.rename_now(file) {
+                Ok(path) if path != *file => rescan.renamed += 1,
+                Ok(_) => {}
+                Err(err) => {
+                    log::error!("Failed to rename file {}: {}", file.display(), err);
+                    rescan.failed += 1;
+                }
+            }
+        }
+
+        rescan
+    }
+
+    fn rename_now(&self, path: &Path) -> Response {
+        if !path.is_file() {
+            return Response::error(format!("Not a file: {}", path.display()));
+        }
+
+        match FileRenamer::new(self.config()).rename_now(path) {
+            Ok(path) => Response::ok(Renamed { path }),
+            Err(err) => Response::error(err),
+        }
+    }
+
+    /// Reads `.env` and the files it points to again, keeping the current
+    /// configuration if the new one is invalid.
+    fn reload_config(&self) -> Response {
+        match self.reloader.reload() {
+            Ok(()) => Response::ok(self.status()),
+            Err(err) => Response::error(err),
+        }
+    }
+
+    fn open_folder(&self) -> Response {
+        let watch_path = self.config().watch_path;
+        log::info!("Opening monitored folder");
+
+        match open::that(&watch_path) {
+            Ok(()) => Response::ok(self.status()),
+            Err(err) => Response::error(format!("Failed to open folder: {}", err)),
+        }
+    }
+
+    fn recent(&self, count: usize) -> Response {
+        let store = StateStore::new(&self.config().state_path());
+
+        match store.records() {
+            Ok(records) => {
+                let skip = records.len().saturating_sub(count);
+                Response::ok(&records[skip..])
+            }
+            Err(err) => Response::error(err),
+        }
+    }
+}
+
+fn modified_within(path: &Path, delay: Duration) -> bool {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .is_ok_and(|modified| {
+            // A time in the future counts as recent
+            SystemTime::now()
+                .duration_since(modified)
+                .map_or(true, |age| age < delay)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn leaves_recently_modified_files() {
+        let path = env::temp_dir().join("autodate-control-recent.pdf");
+        fs::write(&path, b"%PDF").unwrap();
+
+        assert!(modified_within(&path, Duration::from_secs(60)));
+        assert!(!modified_within(&path, Duration::ZERO));
+        assert!(!modified_within(
+            &path.with_extension("missing"),
+            Duration::from_secs(60)
+        ));
+    }
+}
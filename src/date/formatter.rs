@@ -0,0 +1,140 @@
+use crate::date::tokens::replace_tokens;
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Locale, NaiveDate, TimeZone};
+use serde::Deserialize;
+use std::fmt::Display;
+
+/// Specifiers rendered as month or weekday names.
+const NAME_SPECIFIERS: &[char] = &['A', 'a', 'B', 'b', 'h'];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NameCase {
+    #[default]
+    Keep,
+    Lower,
+    Upper,
+    Capitalize,
+}
+
+impl NameCase {
+    pub fn apply(&self, name: &str) -> String {
+        match self {
+            NameCase::Keep => name.to_string(),
+            NameCase::Lower => name.to_lowercase(),
+            NameCase::Upper => name.to_uppercase(),
+            NameCase::Capitalize => {
+                let mut chars = name.chars();
+                match chars.next() {
+                    Some(first) => first.to_uppercase().chain(chars).collect(),
+                    None => String::new(),
+                }
+            }
+        }
+    }
+}
+
+/// Formats dates with chrono's specifiers and the extra period tokens,
+/// rendering month and weekday names in the configured locale and case.
+#[derive(Clone)]
+pub struct DateFormatter {
+    locale: Option<Locale>,
+    name_case: NameCase,
+    fiscal_year_start: u32,
+}
+
+impl DateFormatter {
+    pub fn new(locale: Option<Locale>, name_case: NameCase, fiscal_year_start: u32) -> Self {
+        Self {
+            locale,
+            name_case,
+            fiscal_year_start,
+        }
+    }
+
+    pub fn format<Tz: TimeZone>(&self, date: &DateTime<Tz>, format: &str) -> String
+    where
+        Tz::Offset: Display,
+    {
+        let format = replace_tokens(format, |token| token.render(date, self.fiscal_year_start));
+        let format = self.expand_names(date, &format);
+
+        match self.locale {
+            Some(locale) => date.format_localized(&format, locale).to_string(),
+            None => date.format(&format).to_string(),
+        }
+    }
+
+    pub fn fiscal_year_start(&self) -> u32 {
+        self.fiscal_year_start
+    }
+
+    /// Renders the name of `month` (1-12) as `%B`, `%b` or `%h` would.
+    pub fn month_name(&self, month: u32, specifier: char) -> Option<String> {
+        let date = NaiveDate::from_ymd_opt(2000, month, 1)?.and_hms_opt(0, 0, 0)?;
+        let name = self.format_name(&date.and_utc(), specifier);
+        Some(self.name_case.apply(&name))
+    }
+
+    /// Finds the month (1-12) whose full or abbreviated name is `name`,
+    /// ignoring case.
+    pub fn month_from_name(&self, name: &str) -> Option<u32> {
+        let name = name.to_lowercase();
+
+        (1..=12).find(|month| {
+            ['B', 'b'].into_iter().any(|specifier| {
+                self.month_name(*month, specifier)
+                    .is_some_and(|month_name| month_name.to_lowercase() == name)
+            })
+        })
+    }
+
+    /// Replaces name specifiers with their rendered value so the case rules
+    /// only touch names and not the rest of the format.
+    fn expand_names<Tz: TimeZone>(&self, date: &DateTime<Tz>, format: &str) -> String
+    where
+        Tz::Offset: Display,
+    {
+        let mut expanded = String::with_capacity(format.len());
+        let mut chars = format.chars();
+
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                expanded.push(c);
+                continue;
+            }
+
+            match chars.next() {
+                Some(specifier) if NAME_SPECIFIERS.contains(&specifier) => {
+                    let name = self.format_name(date, specifier);
+                    expanded.push_str(&self.name_case.apply(&name).replace('%', "%%"));
+                }
+                Some(other) => {
+                    expanded.push('%');
+                    expanded.push(other);
+                }
+                None => expanded.push('%'),
+            }
+        }
+
+        expanded
+    }
+
+    /// Whether chrono knows every specifier in `format`. Formatting with an
+    /// unknown one panics.
+    pub fn is_valid_format(format: &str) -> bool {
+        !StrftimeItems::new(format).any(|item| matches!(item, Item::Error))
+    }
+
+    fn format_name<Tz: TimeZone>(&self, date: &DateTime<Tz>, specifier: char) -> String
+    where
+        Tz::Offset: Display,
+    {
+        let format = format!("%{}", specifier);
+
+        match self.locale {
+            Some(locale) => date.format_localized(&format, locale).to_string(),
+            None => date.format(&format).to_string(),
+        }
+    }
+}
@@ -0,0 +1,457 @@
+use crate::config::AppConfig;
+use crate::date::formatter::DateFormatter;
+use crate::date::tokens::{DateToken, replace_tokens};
+use crate::file::sanitizer::Sanitizer;
+use crate::file::sequence::SEQUENCE_FIELD;
+use crate::file::template::FIELD_NAMES;
+use chrono::{NaiveDate, Weekday};
+use regex::{Captures, Regex};
+use std::collections::HashSet;
+use std::iter;
+
+/// Matches the ` (n)` suffix added when a name is already taken.
+const COUNTER_PATTERN: &str = r"(?: \((?P<counter>\d+)\))?";
+
+const NAME_PATTERN: &str = r"\p{L}[\p{L}.]*";
+
+/// Recognises names produced by `FILE_FORMAT` and reads the date back out
+/// of them.
+pub struct NameMatcher {
+    regex: Regex,
+    formatter: DateFormatter,
+}
+
+impl NameMatcher {
+    /// Matchers for the names `config` produces: `DATE_VALIDATION` when
+    /// set, otherwise `FILE_FORMAT` and the format of every rule.
+    pub fn for_config(config: &AppConfig) -> Result<Vec<Self>, regex::Error> {
+        let formatter =
+            DateFormatter::new(config.locale, config.name_case, config.fiscal_year_start);
+
+        if let Some(pattern) = &config.date_validation {
+            return Ok(vec![Self::from_pattern(pattern, &formatter)?]);
+        }
+
+        let sanitizer = Sanitizer::new(&config.name_replacement, config.max_name_length);
+
+        iter::once(config.file_format.as_str())
+            .chain(
+                config
+                    .rules
+                    .iter()
+                    .filter_map(|rule| rule.format.as_deref()),
+            )
+            .map(|format| Self::from_format(format, &formatter, &sanitizer))
+            .collect()
+    }
+
+    /// Compiles `format` into a matcher for the names the renamer would
+    /// generate from it, including collision suffixes.
+    pub fn from_format(
+        format: &str,
+        formatter: &DateFormatter,
+        sanitizer: &Sanitizer,
+    ) -> Result<Self, regex::Error> {
+        let pattern = format!("^{}{}$", compile_format(format, sanitizer), COUNTER_PATTERN);
+
+        Self::new(&pattern, formatter)
+    }
+
+    /// Uses a hand-written pattern. Tokens are expanded, and named groups
+    /// such as `year`, `month` and `day` are used to parse the date.
+    pub fn from_pattern(pattern: &str, formatter: &DateFormatter) -> Result<Self, regex::Error> {
+        let pattern = replace_tokens(pattern, |token| format!("(?:{})", token.pattern()));
+
+        Self::new(&pattern, formatter)
+    }
+
+    fn new(pattern: &str, formatter: &DateFormatter) -> Result<Self, regex::Error> {
+        Ok(Self {
+            regex: Regex::new(pattern)?,
+            formatter: formatter.clone(),
+        })
+    }
+
+    pub fn is_match(&self, stem: &str) -> bool {
+        self.regex.is_match(stem)
+    }
+
+    /// Parses the date in `stem`. Missing parts default to the first month
+    /// or day of the period, e.g. `2025-10` gives October 1st 2025.
+    pub fn parse(&self, stem: &str) -> Option<NaiveDate> {
+        let captures = self.regex.captures(stem)?;
+
+        if let Some(date) = self.parse_calendar(&captures) {
+            return Some(date);
+        }
+
+        if let Some(date) = self.parse_fiscal(&captures) {
+            return Some(date);
+        }
+
+        let iso_year = number(&captures, DateToken::IsoYear.name())?;
+        let iso_week = number(&captures, DateToken::IsoWeek.name())?;
+        NaiveDate::from_isoywd_opt(iso_year as i32, iso_week, Weekday::Mon)
+    }
+
+    fn parse_calendar(&self, captures: &Captures) -> Option<NaiveDate> {
+        let year = number(captures, "year")
+            .map(|year| year as i32)
+            .or_else(|| number(captures, "year2").map(|year| 2000 + year as i32))?;
+
+        let month = match (number(captures, "month"), captures.name("month_name")) {
+            (Some(month), _) => month,
+            (None, Some(name)) => self.formatter.month_from_name(name.as_str())?,
+            (None, None) => number(captures, DateToken::Quarter.name())
+                .map(|quarter| (quarter - 1) * 3 + 1)
+                .or_else(|| number(captures, DateToken::Half.name()).map(|half| (half - 1) * 6 + 1))
+                .unwrap_or(1),
+        };
+
+        let day = number(captures, "day").unwrap_or(1);
+
+        NaiveDate::from_ymd_opt(year, month, day)
+    }
+
+    /// Maps a fiscal year and quarter back to the first day of the quarter.
+    fn parse_fiscal(&self, captures: &Captures) -> Option<NaiveDate> {
+        let fiscal_year = number(captures, DateToken::FiscalYear.name())? as i32;
+        let fiscal_quarter = number(captures, DateToken::FiscalQuarter.name()).unwrap_or(1);
+
+        let fiscal_year_start = self.formatter.fiscal_year_start();
+        let month0 = (fiscal_year_start - 1 + (fiscal_quarter - 1) * 3) % 12;
+        let starts_previous_year = fiscal_year_start > 1 && month0 >= fiscal_year_start - 1;
+        let year = fiscal_year - i32::from(starts_previous_year);
+
+        NaiveDate::from_ymd_opt(year, month0 + 1, 1)
+    }
+}
+
+fn number(captures: &Captures, name: &str) -> Option<u32> {
+    captures.name(name)?.as_str().trim().parse().ok()
+}
+
+/// Translates a strftime format with period tokens and fields into a
+/// regex. The first occurrence of each date part is captured so it can be
+/// parsed back.
+fn compile_format(format: &str, sanitizer: &Sanitizer) -> String {
+    let mut pattern = String::new();
+    let mut captured = HashSet::new();
+    let mut literal = String::new();
+
+    let format = expand_composites(format);
+    let mut chars = format.chars().peekable();
+
+    let flush = |literal: &mut String, pattern: &mut String| {
+        pattern.push_str(&regex::escape(&sanitizer.replace_reserved(literal)));
+        literal.clear();
+    };
+
+    while let Some(c) = chars.next() {
+        match c {
+            '%' => {
+                let padding = chars.next_if(|c| matches!(c, '-' | '_' | '0'));
+
+                let Some(specifier) = chars.next() else {
+                    literal.push('%');
+                    continue;
+                };
+
+                if specifier == '%' {
+                    literal.push('%');
+                    continue;
+                }
+
+                flush(&mut literal, &mut pattern);
+                pattern.push_str(&specifier_pattern(specifier, padding, &mut captured));
+            }
+            '{' => {
+                let rest: String = chars.clone().collect();
+                let placeholder = rest.find('}').and_then(|end| {
+                    placeholder_pattern(&rest[..end], &mut captured).map(|fragment| (fragment, end))
+                });
+
+                match placeholder {
+                    Some((fragment, end)) => {
+                        flush(&mut literal, &mut pattern);
+                        pattern.push_str(&fragment);
+                        chars.nth(end);
+                    }
+                    None => literal.push('{'),
+                }
+            }
+            c => literal.push(c),
+        }
+    }
+
+    flush(&mut literal, &mut pattern);
+    pattern
+}
+
+/// Expands specifiers that are shorthands for several others.
+fn expand_composites(format: &str) -> String {
+    format
+        .replace("%F", "%Y-%m-%d")
+        .replace("%D", "%m/%d/%y")
+        .replace("%T", "%H:%M:%S")
+        .replace("%R", "%H:%M")
+}
+
+fn specifier_pattern(
+    specifier: char,
+    padding: Option<char>,
+    captured: &mut HashSet<&'static str>,
+) -> String {
+    // The space padding of `%e`, `%k` and `%l` is trimmed when it starts
+    // the name
+    let (group, exact, width) = match specifier {
+        'Y' => (Some("year"), r"\d{4}", 4),
+        'y' => (Some("year2"), r"\d{2}", 2),
+        'G' => (Some(DateToken::IsoYear.name()), r"\d{4}", 4),
+        'V' => (Some(DateToken::IsoWeek.name()), r"\d{2}", 2),
+        'g' => (None, r"\d{2}", 2),
+        'C' => (None, r"\d{2}", 2),
+        'm' => (Some("month"), "(?:0[1-9]|1[0-2])", 2),
+        'd' => (Some("day"), "(?:0[1-9]|[12][0-9]|3[01])", 2),
+        'e' => (Some("day"), "(?: ?[1-9]|[12][0-9]|3[01])", 2),
+        'H' => (None, "(?:[01][0-9]|2[0-3])", 2),
+        'k' => (None, "(?: ?[0-9]|1[0-9]|2[0-3])", 2),
+        'I' => (None, "(?:0[1-9]|1[0-2])", 2),
+        'l' => (None, "(?: ?[1-9]|1[0-2])", 2),
+        'M' | 'S' => (None, "[0-5][0-9]", 2),
+        'j' => (None, r"\d{3}", 3),
+        'U' | 'W' => (None, r"\d{2}", 2),
+        'u' => (None, "[1-7]", 1),
+        'w' => (None, "[0-6]", 1),
+        'B' | 'b' | 'h' => return capture("month_name", NAME_PATTERN, captured),
+        'A' | 'a' => return NAME_PATTERN.to_string(),
+        'p' | 'P' => return "[AaPp][Mm]".to_string(),
+        _ => return ".+?".to_string(),
+    };
+
+    let fragment = match padding {
+        Some('-') => format!(r"\d{{1,{}}}", width),
+        Some('_') => format!(r" *\d{{1,{}}}", width),
+        _ => exact.to_string(),
+    };
+
+    match group {
+        Some(group) => capture(group, &fragment, captured),
+        None => fragment,
+    }
+}
+
+/// Period tokens are captured, fields match any text.
+fn placeholder_pattern(name: &str, captured: &mut HashSet<&'static str>) -> Option<String> {
+    if let Some(token) = DateToken::from_name(name) {
+        return Some(capture(token.name(), token.pattern(), captured));
+    }
+
+    if name == SEQUENCE_FIELD {
+        return Some(r"\d+".to_string());
+    }
+
+    FIELD_NAMES.contains(&name).then(|| ".*?".to_string())
+}
+
+fn capture(group: &'static str, fragment: &str, captured: &mut HashSet<&'static str>) -> String {
+    if captured.insert(group) {
+        format!("(?P<{}>{})", group, fragment)
+    } else {
+        format!("(?:{})", fragment)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::date::formatter::NameCase;
+    use chrono::{Locale, TimeZone, Utc};
+
+    fn matcher(format: &str, formatter: &DateFormatter) -> NameMatcher {
+        NameMatcher::from_format(format, formatter, &Sanitizer::new("_", 255))
+            .expect("Valid format")
+    }
+
+    /// Names a file on `date` the way the renamer does, then reads the date
+    /// back out of the name.
+    fn round_trip(format: &str, formatter: &DateFormatter, date: NaiveDate) -> Option<NaiveDate> {
+        let date = Utc.from_utc_datetime(&date.and_hms_opt(10, 30, 0).unwrap());
+        let stem = Sanitizer::new("_", 255).sanitize(&formatter.format(&date, format), "");
+        matcher(format, formatter).parse(&stem)
+    }
+
+    fn ymd(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn reads_back_formatted_dates() {
+        let formatter = DateFormatter::new(None, NameCase::Keep, 1);
+        let date = ymd(2025, 10, 19);
+
+        for format in [
+            "%Y-%m-%d",
+            "%F",
+            "%Y%m%d",
+            "%y%m%d",
+            "%d.%m.%Y",
+            "%Y-%m-%d %H_%M",
+        ] {
+            assert_eq!(
+                round_trip(format, &formatter, date),
+                Some(date),
+                "{}",
+                format
+            );
+        }
+    }
+
+    #[test]
+    fn reads_back_the_start_of_partial_dates() {
+        let formatter = DateFormatter::new(None, NameCase::Keep, 1);
+        let date = ymd(2025, 11, 19);
+
+        assert_eq!(
+            round_trip("%Y-%m", &formatter, date),
+            Some(ymd(2025, 11, 1))
+        );
+        assert_eq!(round_trip("%Y", &formatter, date), Some(ymd(2025, 1, 1)));
+        assert_eq!(
+            round_trip("%Y Q{quarter}", &formatter, date),
+            Some(ymd(2025, 10, 1))
+        );
+        assert_eq!(
+            round_trip("%Y H{half}", &formatter, date),
+            Some(ymd(2025, 7, 1))
+        );
+    }
+
+    #[test]
+    fn reads_back_unpadded_dates() {
+        let formatter = DateFormatter::new(None, NameCase::Keep, 1);
+        let date = ymd(2025, 3, 5);
+
+        assert_eq!(round_trip("%-d.%-m.%Y", &formatter, date), Some(date));
+        assert_eq!(round_trip("%Y-%_m-%_d", &formatter, date), Some(date));
+        assert_eq!(round_trip("%e %m %Y", &formatter, date), Some(date));
+    }
+
+    #[test]
+    fn reads_back_sanitized_literals() {
+        let formatter = DateFormatter::new(None, NameCase::Keep, 1);
+        let date = ymd(2025, 10, 19);
+
+        assert_eq!(round_trip("%d/%m/%Y", &formatter, date), Some(date));
+        assert_eq!(round_trip("%D", &formatter, date), Some(date));
+        assert!(matcher("%d/%m/%Y", &formatter).is_match("19_10_2025"));
+    }
+
+    #[test]
+    fn reads_back_month_names() {
+        let formatter = DateFormatter::new(Some(Locale::es_CO), NameCase::Capitalize, 1);
+        let date = ymd(2025, 10, 19);
+
+        assert_eq!(
+            round_trip("%B %Y", &formatter, date),
+            Some(ymd(2025, 10, 1))
+        );
+        assert_eq!(round_trip("%d %b %Y", &formatter, date), Some(date));
+    }
+
+    #[test]
+    fn reads_back_fiscal_periods() {
+        let formatter = DateFormatter::new(None, NameCase::Keep, 4);
+
+        assert_eq!(
+            round_trip(
+                "FY{fiscal_year} Q{fiscal_quarter}",
+                &formatter,
+                ymd(2025, 10, 19)
+            ),
+            Some(ymd(2025, 10, 1))
+        );
+        assert_eq!(
+            round_trip(
+                "FY{fiscal_year} Q{fiscal_quarter}",
+                &formatter,
+                ymd(2026, 2, 10)
+            ),
+            Some(ymd(2026, 1, 1))
+        );
+        assert_eq!(
+            round_trip("FY{fiscal_year}", &formatter, ymd(2025, 5, 1)),
+            Some(ymd(2025, 4, 1))
+        );
+    }
+
+    #[test]
+    fn reads_back_iso_weeks() {
+        let formatter = DateFormatter::new(None, NameCase::Keep, 1);
+
+        assert_eq!(
+            round_trip("{iso_year}-W{iso_week}", &formatter, ymd(2025, 10, 19)),
+            Some(ymd(2025, 10, 13))
+        );
+        assert_eq!(
+            round_trip("%G-W%V", &formatter, ymd(2027, 1, 1)),
+            Some(ymd(2026, 12, 28))
+        );
+    }
+
+    #[test]
+    fn matches_collision_suffixes() {
+        let formatter = DateFormatter::new(None, NameCase::Keep, 1);
+        let matcher = matcher("%Y-%m", &formatter);
+
+        assert!(matcher.is_match("2025-10"));
+        assert!(matcher.is_match("2025-10 (1)"));
+        assert!(matcher.is_match("2025-10 (12)"));
+        assert_eq!(matcher.parse("2025-10 (3)"), Some(ymd(2025, 10, 1)));
+
+        assert!(!matcher.is_match("2025-10 (a)"));
+        assert!(!matcher.is_match("2025-10(1)"));
+        assert!(!matcher.is_match("2025-13"));
+        assert!(!matcher.is_match("report 2025-10"));
+    }
+
+    #[test]
+    fn matches_fields_and_sequences() {
+        let formatter = DateFormatter::new(None, NameCase::Keep, 1);
+
+        let matcher_with_field = matcher("{vendor} %Y-%m", &formatter);
+        assert_eq!(
+            matcher_with_field.parse("ACME 2025-10 (2)"),
+            Some(ymd(2025, 10, 1))
+        );
+
+        let matcher_with_sequence = matcher("%Y-%m-{seq}", &formatter);
+        assert!(matcher_with_sequence.is_match("2025-10-007"));
+        assert!(!matcher_with_sequence.is_match("2025-10-"));
+    }
+
+    #[test]
+    fn keeps_percent_literals() {
+        let formatter = DateFormatter::new(None, NameCase::Keep, 1);
+        let matcher = matcher("100%% %Y", &formatter);
+
+        assert_eq!(matcher.parse("100% 2025"), Some(ymd(2025, 1, 1)));
+    }
+
+    #[test]
+    fn parses_hand_written_patterns() {
+        let formatter = DateFormatter::new(None, NameCase::Keep, 1);
+        let matcher = NameMatcher::from_pattern(
+            r"^(?P<year>\d{4})(?P<month>\d{2})(?P<day>\d{2})$",
+            &formatter,
+        )
+        .unwrap();
+
+        assert_eq!(matcher.parse("20251019"), Some(ymd(2025, 10, 19)));
+        assert_eq!(matcher.parse("20251319"), None);
+
+        let matcher = NameMatcher::from_pattern(r"^\d{4}-Q{quarter}$", &formatter).unwrap();
+        assert!(matcher.is_match("2025-Q4"));
+        assert!(!matcher.is_match("2025-Q5"));
+    }
+}
@@ -0,0 +1,6 @@
+pub mod formatter;
+pub mod matcher;
+pub mod period;
+pub mod search;
+pub mod timezone;
+pub mod tokens;
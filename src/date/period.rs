@@ -0,0 +1,131 @@
+use chrono::{DateTime, Datelike, FixedOffset, Months, NaiveDate, Weekday};
+
+/// Maps the date a file arrived to the billing period it belongs to.
+pub struct PeriodRules<'a> {
+    grace_days: Option<u32>,
+    offset_months: i32,
+    business_days: bool,
+    holidays: &'a [NaiveDate],
+}
+
+impl<'a> PeriodRules<'a> {
+    pub fn new(
+        grace_days: Option<u32>,
+        offset_months: i32,
+        business_days: bool,
+        holidays: &'a [NaiveDate],
+    ) -> Self {
+        Self {
+            grace_days,
+            offset_months,
+            business_days,
+            holidays,
+        }
+    }
+
+    /// Moves `date` to the previous month when it falls inside the grace
+    /// window, then applies the fixed month offset. A grace of 0 days is no
+    /// window at all, even before the first business day.
+    pub fn apply(&self, date: DateTime<FixedOffset>) -> DateTime<FixedOffset> {
+        let in_grace = self
+            .grace_days
+            .is_some_and(|days| days > 0 && self.elapsed_days(date.date_naive()) <= days);
+
+        let offset = self.offset_months - i32::from(in_grace);
+
+        let shifted = match offset {
+            0 => Some(date),
+            n if n > 0 => date.checked_add_months(Months::new(n.unsigned_abs())),
+            n => date.checked_sub_months(Months::new(n.unsigned_abs())),
+        };
+
+        shifted.unwrap_or(date)
+    }
+
+    /// Days elapsed in the month up to and including `date`, counting only
+    /// business days when configured.
+    fn elapsed_days(&self, date: NaiveDate) -> u32 {
+        if !self.business_days {
+            return date.day();
+        }
+
+        (1..=date.day())
+            .filter_map(|day| date.with_day(day))
+            .filter(|day| self.is_business_day(*day))
+            .count() as u32
+    }
+
+    fn is_business_day(&self, date: NaiveDate) -> bool {
+        !matches!(date.weekday(), Weekday::Sat | Weekday::Sun) && !self.holidays.contains(&date)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(date: &str) -> DateTime<FixedOffset> {
+        DateTime::parse_from_rfc3339(&format!("{}T10:00:00+01:00", date)).unwrap()
+    }
+
+    fn day(date: &str) -> NaiveDate {
+        at(date).date_naive()
+    }
+
+    fn apply(rules: &PeriodRules, date: &str) -> NaiveDate {
+        rules.apply(at(date)).date_naive()
+    }
+
+    #[test]
+    fn moves_dates_inside_the_grace_window() {
+        let rules = PeriodRules::new(Some(5), 0, false, &[]);
+
+        assert_eq!(apply(&rules, "2025-11-03"), day("2025-10-03"));
+        assert_eq!(apply(&rules, "2025-11-05"), day("2025-10-05"));
+        assert_eq!(apply(&rules, "2025-11-06"), day("2025-11-06"));
+        assert_eq!(apply(&rules, "2025-03-01"), day("2025-02-01"));
+    }
+
+    #[test]
+    fn treats_zero_grace_as_no_window() {
+        // November 1st and 2nd 2025 are a weekend, with no business day yet
+        let rules = PeriodRules::new(Some(0), 0, true, &[]);
+
+        assert_eq!(apply(&rules, "2025-11-01"), day("2025-11-01"));
+        assert_eq!(apply(&rules, "2025-11-02"), day("2025-11-02"));
+
+        let rules = PeriodRules::new(Some(0), 0, false, &[]);
+        assert_eq!(apply(&rules, "2025-11-01"), day("2025-11-01"));
+    }
+
+    #[test]
+    fn applies_the_month_offset() {
+        let rules = PeriodRules::new(None, -1, false, &[]);
+        assert_eq!(apply(&rules, "2025-11-20"), day("2025-10-20"));
+
+        let rules = PeriodRules::new(None, 1, false, &[]);
+        assert_eq!(apply(&rules, "2025-01-31"), day("2025-02-28"));
+
+        let rules = PeriodRules::new(Some(3), 1, false, &[]);
+        assert_eq!(apply(&rules, "2025-11-02"), day("2025-11-02"));
+    }
+
+    #[test]
+    fn counts_only_business_days() {
+        let rules = PeriodRules::new(Some(2), 0, true, &[]);
+
+        // Sat 1st and Sun 2nd don't count, Mon 3rd and Tue 4th do
+        assert_eq!(apply(&rules, "2025-11-01"), day("2025-10-01"));
+        assert_eq!(apply(&rules, "2025-11-04"), day("2025-10-04"));
+        assert_eq!(apply(&rules, "2025-11-05"), day("2025-11-05"));
+    }
+
+    #[test]
+    fn skips_holidays() {
+        let holidays = [day("2025-11-03")];
+        let rules = PeriodRules::new(Some(2), 0, true, &holidays);
+
+        assert_eq!(apply(&rules, "2025-11-05"), day("2025-10-05"));
+        assert_eq!(apply(&rules, "2025-11-06"), day("2025-11-06"));
+    }
+}
@@ -0,0 +1,25 @@
+use crate::date::formatter::DateFormatter;
+use chrono::NaiveDate;
+use regex::Regex;
+
+/// Returns the first date matched by `patterns`, which name the parts of
+/// the date with `year`, `month` and `day` groups. Months can be numbers or
+/// names in the configured locale.
+pub fn find_date(text: &str, patterns: &[Regex], formatter: &DateFormatter) -> Option<NaiveDate> {
+    patterns.iter().find_map(|pattern| {
+        pattern.captures_iter(text).find_map(|captures| {
+            let year: i32 = captures.name("year")?.as_str().parse().ok()?;
+            let year = if year < 100 { 2000 + year } else { year };
+
+            let month = captures.name("month")?.as_str();
+            let month = month
+                .parse()
+                .ok()
+                .or_else(|| formatter.month_from_name(month))?;
+
+            let day = captures.name("day")?.as_str().parse().ok()?;
+
+            NaiveDate::from_ymd_opt(year, month, day)
+        })
+    })
+}
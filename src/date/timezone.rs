@@ -0,0 +1,142 @@
+use chrono::{DateTime, FixedOffset, Local, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Deserializer};
+use std::str::FromStr;
+
+/// Timezone used to compute every date that ends up in a file name.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Timezone {
+    #[default]
+    Local,
+    Named(Tz),
+    Fixed(FixedOffset),
+}
+
+impl Timezone {
+    pub fn now(&self) -> DateTime<FixedOffset> {
+        self.convert(Utc::now())
+    }
+
+    pub fn convert(&self, date: DateTime<Utc>) -> DateTime<FixedOffset> {
+        match self {
+            Timezone::Local => date.with_timezone(&Local).fixed_offset(),
+            Timezone::Named(tz) => date.with_timezone(tz).fixed_offset(),
+            Timezone::Fixed(offset) => date.with_timezone(offset),
+        }
+    }
+
+    /// Interprets a date and time without offset as wall time in this
+    /// timezone.
+    pub fn localize(&self, date: NaiveDateTime) -> Option<DateTime<FixedOffset>> {
+        match self {
+            Timezone::Local => Local
+                .from_local_datetime(&date)
+                .earliest()
+                .map(|date| date.fixed_offset()),
+            Timezone::Named(tz) => tz
+                .from_local_datetime(&date)
+                .earliest()
+                .map(|date| date.fixed_offset()),
+            Timezone::Fixed(offset) => offset.from_local_datetime(&date).earliest(),
+        }
+    }
+}
+
+impl FromStr for Timezone {
+    type Err = String;
+
+    /// Accepts `local`, an IANA name such as `America/Bogota` or `UTC`, or
+    /// a fixed offset such as `-05:00` or `+0530`.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+
+        if value.is_empty() || value.eq_ignore_ascii_case("local") {
+            return Ok(Timezone::Local);
+        }
+
+        if value.starts_with(['+', '-']) {
+            return parse_offset(value)
+                .map(Timezone::Fixed)
+                .ok_or_else(|| format!("invalid UTC offset `{}`", value));
+        }
+
+        Tz::from_str(value)
+            .map(Timezone::Named)
+            .map_err(|_| format!("unknown timezone `{}`", value))
+    }
+}
+
+impl<'de> Deserialize<'de> for Timezone {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = String::deserialize(deserializer)?;
+        Timezone::from_str(&value).map_err(serde::de::Error::custom)
+    }
+}
+
+fn parse_offset(value: &str) -> Option<FixedOffset> {
+    let sign = if value.starts_with('-') { -1 } else { 1 };
+    let digits: String = value[1..].chars().filter(|c| *c != ':').collect();
+
+    if !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let (hours, minutes) = match digits.len() {
+        1 | 2 => (digits.parse::<i32>().ok()?, 0),
+        4 => (
+            digits[..2].parse::<i32>().ok()?,
+            digits[2..].parse::<i32>().ok()?,
+        ),
+        _ => return None,
+    };
+
+    if minutes >= 60 {
+        return None;
+    }
+
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn offset(seconds: i32) -> Option<FixedOffset> {
+        FixedOffset::east_opt(seconds)
+    }
+
+    #[test]
+    fn parses_offsets() {
+        assert_eq!(parse_offset("-05:00"), offset(-5 * 3600));
+        assert_eq!(parse_offset("+0530"), offset(5 * 3600 + 30 * 60));
+        assert_eq!(parse_offset("+5"), offset(5 * 3600));
+        assert_eq!(parse_offset("-00:00"), offset(0));
+    }
+
+    #[test]
+    fn rejects_invalid_offsets() {
+        assert_eq!(parse_offset("+05:60"), None);
+        assert_eq!(parse_offset("+24:00"), None);
+        assert_eq!(parse_offset("+053"), None);
+        assert_eq!(parse_offset("+5h"), None);
+        assert_eq!(parse_offset("-"), None);
+    }
+
+    #[test]
+    fn parses_timezones() {
+        assert_eq!(Timezone::from_str(""), Ok(Timezone::Local));
+        assert_eq!(Timezone::from_str("LOCAL"), Ok(Timezone::Local));
+        assert_eq!(
+            Timezone::from_str("America/Bogota"),
+            Ok(Timezone::Named(chrono_tz::America::Bogota))
+        );
+        assert_eq!(
+            Timezone::from_str(" -05:00 "),
+            Ok(Timezone::Fixed(offset(-5 * 3600).unwrap()))
+        );
+        assert!(Timezone::from_str("Mars/Olympus").is_err());
+    }
+}
@@ -0,0 +1,151 @@
+use crate::file::template::replace_placeholders;
+use chrono::{DateTime, Datelike, TimeZone};
+
+/// Period tokens that strftime can't express, written as `{name}` in
+/// `FILE_FORMAT` and `DATE_VALIDATION`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DateToken {
+    Quarter,
+    Half,
+    FiscalYear,
+    FiscalQuarter,
+    IsoYear,
+    IsoWeek,
+}
+
+impl DateToken {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "quarter" => Some(DateToken::Quarter),
+            "half" => Some(DateToken::Half),
+            "fiscal_year" => Some(DateToken::FiscalYear),
+            "fiscal_quarter" => Some(DateToken::FiscalQuarter),
+            "iso_year" => Some(DateToken::IsoYear),
+            "iso_week" => Some(DateToken::IsoWeek),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DateToken::Quarter => "quarter",
+            DateToken::Half => "half",
+            DateToken::FiscalYear => "fiscal_year",
+            DateToken::FiscalQuarter => "fiscal_quarter",
+            DateToken::IsoYear => "iso_year",
+            DateToken::IsoWeek => "iso_week",
+        }
+    }
+
+    /// Renders the token. The fiscal year is labelled with the calendar
+    /// year in which it ends, so with an April start, October 2025 is in
+    /// fiscal year 2026.
+    pub fn render<Tz: TimeZone>(&self, date: &DateTime<Tz>, fiscal_year_start: u32) -> String {
+        let month0 = date.month0();
+        let fiscal_month0 = (month0 + 12 - (fiscal_year_start - 1)) % 12;
+
+        match self {
+            DateToken::Quarter => (month0 / 3 + 1).to_string(),
+            DateToken::Half => (month0 / 6 + 1).to_string(),
+            DateToken::FiscalYear => {
+                let ends_next_year = fiscal_year_start > 1 && month0 >= fiscal_year_start - 1;
+                (date.year() + i32::from(ends_next_year)).to_string()
+            }
+            DateToken::FiscalQuarter => (fiscal_month0 / 3 + 1).to_string(),
+            DateToken::IsoYear => date.iso_week().year().to_string(),
+            DateToken::IsoWeek => format!("{:02}", date.iso_week().week()),
+        }
+    }
+
+    /// Regex fragment matching any rendered value of the token.
+    pub fn pattern(&self) -> &'static str {
+        match self {
+            DateToken::Quarter | DateToken::FiscalQuarter => "[1-4]",
+            DateToken::Half => "[12]",
+            DateToken::FiscalYear | DateToken::IsoYear => r"\d{4}",
+            DateToken::IsoWeek => "(?:0[1-9]|[1-4][0-9]|5[0-3])",
+        }
+    }
+}
+
+/// Replaces every known `{token}` in `text` with the result of `replace`.
+pub fn replace_tokens(text: &str, mut replace: impl FnMut(DateToken) -> String) -> String {
+    replace_placeholders(text, |name| DateToken::from_name(name).map(&mut replace))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{NaiveDate, Utc};
+
+    fn render(token: DateToken, year: i32, month: u32, day: u32, fiscal_year_start: u32) -> String {
+        let date = NaiveDate::from_ymd_opt(year, month, day)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        token.render(&Utc.from_utc_datetime(&date), fiscal_year_start)
+    }
+
+    #[test]
+    fn renders_calendar_periods() {
+        assert_eq!(render(DateToken::Quarter, 2025, 1, 1, 1), "1");
+        assert_eq!(render(DateToken::Quarter, 2025, 10, 19, 1), "4");
+        assert_eq!(render(DateToken::Half, 2025, 6, 30, 1), "1");
+        assert_eq!(render(DateToken::Half, 2025, 7, 1, 1), "2");
+    }
+
+    #[test]
+    fn renders_fiscal_periods() {
+        // A fiscal year starting in January is the calendar year
+        assert_eq!(render(DateToken::FiscalYear, 2025, 10, 19, 1), "2025");
+        assert_eq!(render(DateToken::FiscalQuarter, 2025, 10, 19, 1), "4");
+
+        // Starting in April, it is named after the year it ends in
+        assert_eq!(render(DateToken::FiscalYear, 2025, 3, 31, 4), "2025");
+        assert_eq!(render(DateToken::FiscalYear, 2025, 4, 1, 4), "2026");
+        assert_eq!(render(DateToken::FiscalQuarter, 2025, 4, 1, 4), "1");
+        assert_eq!(render(DateToken::FiscalQuarter, 2025, 10, 19, 4), "3");
+        assert_eq!(render(DateToken::FiscalQuarter, 2026, 3, 31, 4), "4");
+    }
+
+    #[test]
+    fn renders_iso_weeks() {
+        assert_eq!(render(DateToken::IsoWeek, 2025, 10, 19, 1), "42");
+        assert_eq!(render(DateToken::IsoWeek, 2025, 1, 1, 1), "01");
+
+        // The first days of January can belong to the last week of the
+        // previous ISO year
+        assert_eq!(render(DateToken::IsoYear, 2027, 1, 1, 1), "2026");
+        assert_eq!(render(DateToken::IsoWeek, 2027, 1, 1, 1), "53");
+    }
+
+    #[test]
+    fn rendered_values_match_their_pattern() {
+        let tokens = [
+            DateToken::Quarter,
+            DateToken::Half,
+            DateToken::FiscalYear,
+            DateToken::FiscalQuarter,
+            DateToken::IsoYear,
+            DateToken::IsoWeek,
+        ];
+
+        for token in tokens {
+            let pattern = regex::Regex::new(&format!("^{}$", token.pattern())).unwrap();
+
+            for month in 1..=12 {
+                let value = render(token, 2025, month, 15, 7);
+                assert!(pattern.is_match(&value), "{:?} {}", token, value);
+            }
+        }
+    }
+
+    #[test]
+    fn replaces_only_known_tokens() {
+        let replaced = replace_tokens("{quarter} {vendor} {half} {", |token| {
+            token.name().to_uppercase()
+        });
+
+        assert_eq!(replaced, "QUARTER {vendor} HALF {");
+    }
+}
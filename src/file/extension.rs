@@ -0,0 +1,60 @@
+use serde::{Deserialize, Deserializer};
+
+/// Splits a file name into its stem and extension.
+///
+/// Known compound extensions (e.g. `tar.gz`) are kept together, and names
+/// without an extension, as well as dotfiles like `.env`, return `None`.
+pub fn split_file_name<'a>(
+    file_name: &'a str,
+    compound_extensions: &[String],
+) -> (&'a str, Option<&'a str>) {
+    let lowercase = file_name.to_lowercase();
+
+    let compound = compound_extensions
+        .iter()
+        .filter(|ext| !ext.is_empty())
+        .filter(|ext| lowercase.ends_with(&format!(".{}", ext.to_lowercase())))
+        .map(|ext| file_name.len() - ext.len() - 1)
+        .filter(|&index| index > 0)
+        .min();
+
+    if let Some(index) = compound {
+        return (&file_name[..index], Some(&file_name[index + 1..]));
+    }
+
+    match file_name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() && !extension.is_empty() => {
+            (stem, Some(extension))
+        }
+        _ => (file_name, None),
+    }
+}
+
+/// Extensions of files that belong together, written as `pdf+xml`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtensionSet(Vec<String>);
+
+impl ExtensionSet {
+    pub fn contains(&self, extension: &str) -> bool {
+        self.0
+            .iter()
+            .any(|member| member.eq_ignore_ascii_case(extension))
+    }
+}
+
+impl<'de> Deserialize<'de> for ExtensionSet {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = String::deserialize(deserializer)?;
+
+        let extensions = value
+            .split('+')
+            .map(|extension| extension.trim().trim_start_matches('.').to_string())
+            .filter(|extension| !extension.is_empty())
+            .collect();
+
+        Ok(ExtensionSet(extensions))
+    }
+}
@@ -1,3 +1,11 @@
+pub mod extension;
 pub mod handler;
+pub mod quarantine;
 pub mod renamer;
+pub mod rules;
+pub mod sanitizer;
+pub mod sequence;
+pub mod sniffer;
+pub mod template;
+pub mod unpacker;
 pub mod watcher;
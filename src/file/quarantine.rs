@@ -0,0 +1,120 @@
+use crate::file::extension::split_file_name;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const REASON_SUFFIX: &str = ".reason.txt";
+
+/// Moves `path` into `dir`, keeping its name when possible, and records
+/// why next to it in a `<name>.reason.txt` file.
+pub fn quarantine(
+    path: &Path,
+    dir: &Path,
+    reason: &str,
+    compound_extensions: &[String],
+) -> io::Result<PathBuf> {
+    fs::create_dir_all(dir)?;
+
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "File has no name"))?
+        .to_string_lossy()
+        .to_string();
+
+    let (stem, extension) = split_file_name(&file_name, compound_extensions);
+
+    let target = (0..)
+        .map(|n| match (n, extension) {
+            (0, _) => dir.join(&file_name),
+            (n, Some(extension)) => dir.join(format!("{} ({}).{}", stem, n, extension)),
+            (n, None) => dir.join(format!("{} ({})", stem, n)),
+        })
+        .find(|path| !path.exists() && !reason_path(path).exists())
+        .expect("Infinite iterator always yields a path");
+
+    move_file(path, &target)?;
+
+    fs::write(
+        reason_path(&target),
+        format!("Original path: {}\nReason: {}\n", path.display(), reason),
+    )?;
+
+    Ok(target)
+}
+
+/// Renames `from` to `to`, copying it when `QUARANTINE_PATH` is on another
+/// volume, where a rename is not possible.
+fn move_file(from: &Path, to: &Path) -> io::Result<()> {
+    match fs::rename(from, to) {
+        Err(err) if err.kind() == io::ErrorKind::CrossesDevices => {
+            fs::copy(from, to)?;
+
+            if let Err(err) = fs::remove_file(from) {
+                // Keep a single copy
+                let _ = fs::remove_file(to);
+                return Err(err);
+            }
+
+            Ok(())
+        }
+        result => result,
+    }
+}
+
+fn reason_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(REASON_SUFFIX);
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn keeps_the_extension_on_collisions() {
+        let root = env::temp_dir().join("autodate-quarantine-tests");
+        let _ = fs::remove_dir_all(&root);
+        let dir = root.join("quarantine");
+        fs::create_dir_all(&root).unwrap();
+        let compound = ["tar.gz".to_string()];
+
+        let mut targets = Vec::new();
+
+        for name in [
+            "invoice.pdf",
+            "invoice.pdf",
+            "backup.tar.gz",
+            "backup.tar.gz",
+            "README",
+        ] {
+            let path = root.join(name);
+            fs::write(&path, b"x").unwrap();
+            targets.push(quarantine(&path, &dir, "not allowed", &compound).unwrap());
+            assert!(!path.exists());
+        }
+
+        fs::write(root.join("README"), b"x").unwrap();
+        targets.push(quarantine(&root.join("README"), &dir, "not allowed", &compound).unwrap());
+
+        let names: Vec<_> = targets
+            .iter()
+            .map(|target| target.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "invoice.pdf",
+                "invoice (1).pdf",
+                "backup.tar.gz",
+                "backup (1).tar.gz",
+                "README",
+                "README (1)"
+            ]
+        );
+
+        let reason = fs::read_to_string(dir.join("invoice (1).pdf.reason.txt")).unwrap();
+        assert!(reason.contains("Reason: not allowed"));
+    }
+}
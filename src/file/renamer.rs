@@ -1,8 +1,27 @@
+use crate::audit::{AuditEvent, AuditLog};
 use crate::config::AppConfig;
-use chrono::Local;
-use regex::Regex;
+use crate::date::formatter::DateFormatter;
+use crate::date::matcher::NameMatcher;
+use crate::date::period::PeriodRules;
+use crate::file::extension::split_file_name;
+use crate::file::quarantine::quarantine;
+use crate::file::rules::Subject;
+use crate::file::sanitizer::Sanitizer;
+use crate::file::sequence::{SEQUENCE_FIELD, SequenceError, SequenceStore};
+use crate::file::sniffer::{FileType, is_generic_extension};
+use crate::file::template::{Fields, render_fields, render_folder_fields};
+use crate::file::unpacker::{UnpackError, Unpacker, is_staging, remove_staging};
+use crate::logging;
+use crate::rotation::is_rotation_of;
+use crate::source::{DateSource, Document};
+use crate::state::{Outcome, Record, StateStore, fingerprint};
+use chrono::{DateTime, FixedOffset, Months, NaiveDate, TimeDelta};
+use std::borrow::Cow;
+use std::fs;
+use std::io;
 use std::iter;
-use std::path::{Path, PathBuf};
+use std::path::{self, Component, Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
 use std::thread;
 use std::time::Duration;
 use thiserror::Error;
@@ -12,9 +31,6 @@ pub enum RenameError {
     #[error("File has no stem")]
     NoStem,
 
-    #[error("File has no extension")]
-    NoExtension,
-
     #[error("File has no parent directory")]
     NoParentDirectory,
 
@@ -26,55 +42,908 @@ pub enum RenameError {
 
     #[error("Invalid date format")]
     InvalidDateFormat,
+
+    #[error("Failed to unpack archive: {0}")]
+    UnpackError(#[from] UnpackError),
+
+    #[error("{0}")]
+    SequenceError(#[from] SequenceError),
+
+    #[error("Destination {} is outside WATCH_PATH", .0.display())]
+    OutsideWatchPath(PathBuf),
 }
 
 pub struct FileRenamer {
     config: AppConfig,
 }
 
+/// A file that is going to be renamed, with what was learnt about it.
+struct Candidate {
+    path: PathBuf,
+    stem: String,
+    file_type: Option<FileType>,
+    extension: Option<String>,
+    document: Document,
+}
+
+/// Held while a file is renamed, so the watcher and control requests never
+/// work on the same files at once.
+static RENAME_LOCK: Mutex<()> = Mutex::new(());
+
+/// Destination used with `vendor_folders` when no rule sets one.
+const VENDOR_DESTINATION: &str = "{vendor}";
+
+/// How a set of files is named: by the first rule that matches it, or by
+/// the defaults.
+struct Naming<'a> {
+    rule: Option<&'a str>,
+    format: &'a str,
+    sources: Cow<'a, [DateSource]>,
+    destination: Option<&'a str>,
+}
+
+impl Naming<'_> {
+    /// Dates the set by the email's `Date` header before anything else, so
+    /// an email and its attachments are named after when it was sent.
+    fn email_first(mut self) -> Self {
+        self.sources = iter::once(DateSource::Email)
+            .chain(
+                self.sources
+                    .iter()
+                    .copied()
+                    .filter(|source| *source != DateSource::Email),
+            )
+            .collect();
+        self
+    }
+}
+
+/// What was decided about a file, for the state store and audit log.
+struct Decision<'a> {
+    file: &'a Path,
+    path: Option<&'a Path>,
+    rule: Option<&'a str>,
+    source: Option<DateSource>,
+    date: Option<NaiveDate>,
+    collision: Option<usize>,
+    outcome: Outcome,
+    error: Option<String>,
+}
+
+impl<'a> Decision<'a> {
+    fn new(file: &'a Path, outcome: Outcome) -> Self {
+        Self {
+            file,
+            path: None,
+            rule: None,
+            source: None,
+            date: None,
+            collision: None,
+            outcome,
+            error: None,
+        }
+    }
+}
+
+enum Inspection {
+    Skip,
+    Quarantined(PathBuf),
+    Rename(Box<Candidate>),
+}
+
 impl FileRenamer {
     pub fn new(config: AppConfig) -> Self {
         Self { config }
     }
 
+    /// Renames a new file once `delay_seconds` have passed, giving whatever
+    /// is writing it time to finish.
     pub fn rename_file(&self, path: &Path) -> Result<PathBuf, RenameError> {
         thread::sleep(Duration::from_secs(self.config.delay_seconds));
+        self.rename_now(path)
+    }
+
+    /// Renames a file that is already complete, without waiting.
+    pub fn rename_now(&self, path: &Path) -> Result<PathBuf, RenameError> {
+        let _guard = RENAME_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+        let result = self.process(path);
+
+        if let Err(err) = &result {
+            self.record(Decision {
+                error: Some(err.to_string()),
+                ..Decision::new(path, Outcome::Failed)
+            });
+        }
+
+        result
+    }
+
+    fn process(&self, path: &Path) -> Result<PathBuf, RenameError> {
+        if path.starts_with(self.config.quarantine_dir())
+            || is_staging(path)
+            || self.is_written_by_app(path)
+        {
+            log::debug!("Ignoring internal file: {}", path.display());
+            return Ok(path.to_path_buf());
+        }
+
+        if !path.exists() {
+            log::debug!("File no longer exists, skipping: {}", path.display());
+            return Ok(path.to_path_buf());
+        }
 
-        // Generate new filename with current date
         let parent = path.parent().ok_or(RenameError::NoParentDirectory)?;
 
-        let date = Local::now().format(&self.config.file_format);
+        let candidate = match self.inspect(path, true)? {
+            Inspection::Skip => return Ok(path.to_path_buf()),
+            Inspection::Quarantined(quarantined) => return Ok(quarantined),
+            Inspection::Rename(candidate) => *candidate,
+        };
 
-        let stem = path
-            .file_stem()
-            .and_then(|stem| stem.to_str())
-            .ok_or(RenameError::NoStem)?;
+        let extension = candidate.extension.as_deref();
+
+        let unpack = match candidate.file_type {
+            Some(FileType::Zip) => {
+                self.config.unpack_archives && is_labelled_as(FileType::Zip, extension)
+            }
+            Some(FileType::Eml) => {
+                self.config.extract_attachments && is_labelled_as(FileType::Eml, extension)
+            }
+            _ => {
+                if self.config.extract_attachments
+                    && extension.is_some_and(|extension| extension.eq_ignore_ascii_case("msg"))
+                {
+                    log::warn!(
+                        "Outlook .msg files are not supported, renaming {} without its attachments",
+                        path.display()
+                    );
+                }
+                false
+            }
+        };
+
+        if unpack {
+            // A rule can keep an archive from being extracted
+            if self.naming(std::slice::from_ref(&candidate)).is_none() {
+                return Ok(path.to_path_buf());
+            }
 
-        let extension = path
-            .extension()
-            .and_then(|ext| ext.to_str())
-            .ok_or(RenameError::NoExtension)?;
+            return self.unpack(candidate, parent);
+        }
+
+        let mut members = self.find_group(&candidate, parent)?;
+
+        if !members.is_empty() {
+            log::info!("Renaming {} related files together", members.len() + 1);
+        }
 
-        let is_valid_name = Regex::new(&self.config.date_validation)
-            .map_err(|_| RenameError::InvalidDateFormat)?
-            .is_match(stem);
+        members.insert(0, candidate);
 
-        if is_valid_name {
-            log::info!("File already has a valid date in the name, skipping");
+        let Some(naming) = self.naming(&members) else {
             return Ok(path.to_path_buf());
+        };
+
+        let mut renamed = self.rename_set(members, parent, &naming)?;
+        Ok(renamed.remove(0))
+    }
+
+    /// Finds the files in `dir` that belong with `candidate`: same stem, an
+    /// extension in the same `group_extensions` set, and modified within
+    /// `group_window_seconds` of it.
+    fn find_group(&self, candidate: &Candidate, dir: &Path) -> Result<Vec<Candidate>, RenameError> {
+        let Some(set) = candidate.extension.as_deref().and_then(|extension| {
+            self.config
+                .group_extensions
+                .iter()
+                .find(|set| set.contains(extension))
+        }) else {
+            return Ok(Vec::new());
+        };
+
+        let modified = fs::metadata(&candidate.path)?.modified()?;
+        let window = Duration::from_secs(self.config.group_window_seconds);
+
+        let mut members = Vec::new();
+
+        // A sibling that vanishes or can't be read is left out of the group
+        // rather than failing the candidate
+        for entry in fs::read_dir(dir)? {
+            let Ok(entry) = entry else {
+                continue;
+            };
+            let path = entry.path();
+
+            if path == candidate.path || !path.is_file() {
+                continue;
+            }
+
+            let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+
+            let (stem, extension) = split_file_name(file_name, &self.config.compound_extensions);
+
+            if !stem.eq_ignore_ascii_case(&candidate.stem)
+                || !extension.is_some_and(|extension| set.contains(extension))
+            {
+                continue;
+            }
+
+            let Ok(other) = fs::metadata(&path).and_then(|metadata| metadata.modified()) else {
+                continue;
+            };
+            let elapsed = modified
+                .duration_since(other)
+                .or_else(|_| other.duration_since(modified))
+                .unwrap_or_default();
+
+            if elapsed > window {
+                continue;
+            }
+
+            match self.inspect(&path, false) {
+                Ok(Inspection::Rename(member)) => members.push(*member),
+                Ok(_) => {}
+                Err(err) => log::warn!("Leaving {} out of the group: {}", path.display(), err),
+            }
+        }
+
+        Ok(members)
+    }
+
+    /// Decides whether `path` needs renaming, quarantining it if its content
+    /// is not allowed.
+    fn inspect(&self, path: &Path, check_name: bool) -> Result<Inspection, RenameError> {
+        let file_name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or(RenameError::NoStem)?;
+
+        let (stem, extension) = split_file_name(file_name, &self.config.compound_extensions);
+
+        if check_name {
+            let matchers = NameMatcher::for_config(&self.config)
+                .map_err(|_| RenameError::InvalidDateFormat)?;
+
+            if let Some(matcher) = matchers.iter().find(|matcher| matcher.is_match(stem)) {
+                match matcher.parse(stem) {
+                    Some(date) => log::info!("File is already named for {}, skipping", date),
+                    None => log::info!("File already has a valid date in the name, skipping"),
+                }
+                self.record(Decision {
+                    error: Some("already named".to_string()),
+                    ..Decision::new(path, Outcome::Skipped)
+                });
+                return Ok(Inspection::Skip);
+            }
+        }
+
+        let file_type = FileType::detect(path)?;
+
+        if let Err(reason) = self.check_allowed(file_type) {
+            let quarantined = quarantine(
+                path,
+                &self.config.quarantine_dir(),
+                &reason,
+                &self.config.compound_extensions,
+            )?;
+            log::warn!(
+                "File rejected ({}), moved to: {}",
+                reason,
+                quarantined.display()
+            );
+            self.record(Decision {
+                path: Some(&quarantined),
+                error: Some(reason),
+                ..Decision::new(path, Outcome::Quarantined)
+            });
+            return Ok(Inspection::Quarantined(quarantined));
+        }
+
+        Ok(Inspection::Rename(Box::new(Candidate {
+            path: path.to_path_buf(),
+            stem: stem.to_string(),
+            file_type,
+            extension: self.resolve_extension(file_type, extension),
+            document: Document::load(path, file_type, &self.config),
+        })))
+    }
+
+    /// Extracts `archive`, a zip file or an email with attachments, and
+    /// renames its contents as a set. Zip files are then deleted or renamed
+    /// along with their contents; emails are always kept.
+    fn unpack(&self, archive: Candidate, parent: &Path) -> Result<PathBuf, RenameError> {
+        let unpacker = Unpacker::new(
+            self.config.max_archive_entries,
+            self.config.max_unpacked_size,
+        );
+
+        let (staging, files, keep) = match archive.file_type {
+            Some(FileType::Eml) => {
+                let (staging, files) = unpacker.unpack_email(&archive.path)?;
+                (staging, files, true)
+            }
+            _ => {
+                let (staging, files) = unpacker.unpack(&archive.path)?;
+                (staging, files, self.config.keep_archives)
+            }
+        };
+
+        let result = self.rename_unpacked(archive, &files, parent, keep);
+        remove_staging(&staging);
+        result
+    }
+
+    fn rename_unpacked(
+        &self,
+        archive: Candidate,
+        files: &[PathBuf],
+        parent: &Path,
+        keep: bool,
+    ) -> Result<PathBuf, RenameError> {
+        let mut members = Vec::new();
+
+        for file in files {
+            if let Inspection::Rename(candidate) = self.inspect(file, false)? {
+                members.push(*candidate);
+            }
+        }
+
+        let archive_path = archive.path.clone();
+        let email = archive.file_type == Some(FileType::Eml);
+
+        if members.is_empty() {
+            log::info!("Archive has no files to extract, renaming it instead");
+            let members = vec![archive];
+
+            let Some(naming) = self.unpacked_naming(&members, email) else {
+                return Ok(archive_path);
+            };
+
+            let mut renamed = self.rename_set(members, parent, &naming)?;
+            return Ok(renamed.remove(0));
+        }
+
+        if keep {
+            members.push(archive);
+        }
+
+        let Some(naming) = self.unpacked_naming(&members, email) else {
+            return Ok(archive_path);
+        };
+
+        let renamed = self.rename_set(members, parent, &naming)?;
+
+        if !keep {
+            fs::remove_file(&archive_path)?;
+            log::info!("Removed archive: {}", archive_path.display());
         }
 
-        let new_path = iter::once(format!("{}.{}", date, extension))
-            .chain((1..).map(|n| format!("{} ({}).{}", date, n, extension)))
-            .map(|filename| parent.join(filename))
-            .find(|path| !path.exists())
+        Ok(renamed[0].clone())
+    }
+
+    /// Names the contents of an archive or email like any other set, except
+    /// that an email's date comes first.
+    fn unpacked_naming(&self, members: &[Candidate], email: bool) -> Option<Naming<'_>> {
+        let naming = self.naming(members)?;
+        Some(if email { naming.email_first() } else { naming })
+    }
+
+    /// Picks how `members` are named from the first rule that matches the
+    /// first of them, seeing the fields of the whole set. Returns `None`
+    /// when that rule says to leave them alone.
+    fn naming(&self, members: &[Candidate]) -> Option<Naming<'_>> {
+        let first = members.first()?;
+        let fields = merge_fields(members);
+
+        let subject = Subject {
+            path: &first.path,
+            extension: first.extension.as_deref(),
+            file_type: first.file_type,
+            fields: &fields,
+        };
+
+        let default_destination = self.config.vendor_folders.then_some(VENDOR_DESTINATION);
+
+        let Some(rule) = self.config.rules.find(&subject) else {
+            return Some(Naming {
+                rule: None,
+                format: &self.config.file_format,
+                sources: Cow::Borrowed(&self.config.date_sources),
+                destination: default_destination,
+            });
+        };
+
+        if rule.skip {
+            log::info!("Rule {} matched, leaving the file as it is", rule.label());
+
+            for member in members {
+                self.record(Decision {
+                    rule: Some(rule.label()),
+                    ..Decision::new(&member.path, Outcome::Skipped)
+                });
+            }
+            return None;
+        }
+
+        log::info!("Applying rule {}", rule.label());
+
+        Some(Naming {
+            rule: Some(rule.label()),
+            format: rule.format.as_deref().unwrap_or(&self.config.file_format),
+            sources: Cow::Borrowed(
+                rule.date_sources
+                    .as_deref()
+                    .unwrap_or(&self.config.date_sources),
+            ),
+            destination: rule.destination.as_deref().or(default_destination),
+        })
+    }
+
+    /// Gives every member the same base name and counter and moves them
+    /// into `dir`, or the rule's destination, together. If one move fails,
+    /// the others are undone.
+    fn rename_set(
+        &self,
+        members: Vec<Candidate>,
+        dir: &Path,
+        naming: &Naming,
+    ) -> Result<Vec<PathBuf>, RenameError> {
+        let fields = merge_fields(&members);
+        let documents: Vec<&Document> = members.iter().map(|member| &member.document).collect();
+        let formatter = self.formatter();
+        let sanitizer = Sanitizer::new(&self.config.name_replacement, self.config.max_name_length);
+
+        // Generate new filename with the documents' date and fields
+        let format = render_fields(naming.format, &fields);
+        let (date, source) = self.resolve_date(&documents, &naming.sources);
+        let mut base = formatter.format(&date, &format);
+
+        // `{seq}` takes the next number of the sequence for this name, once
+        // the date and fields are filled in
+        let placeholder = format!("{{{}}}", SEQUENCE_FIELD);
+        let sequence = if base.contains(&placeholder) {
+            let store = SequenceStore::open(&self.config.sequence_path())?;
+            let key = format!("{}|{}", self.config.watch_path, base);
+            let value = store.peek(&key);
+
+            base = base.replace(
+                &placeholder,
+                &format!("{:0width$}", value, width = self.config.sequence_digits),
+            );
+            Some((store, key, value))
+        } else {
+            None
+        };
+
+        // A destination whose fields are all missing leaves the files where
+        // they are
+        let destination = naming.destination.and_then(|template| {
+            let destination = render_folder_fields(template, &fields, &sanitizer);
+            let destination = formatter.format(&date, &destination);
+            (!destination.trim().is_empty()).then_some((template, destination))
+        });
+
+        let dir = match destination {
+            Some((template, destination)) => {
+                let dir = self.destination_dir(template, &destination, &sanitizer)?;
+                fs::create_dir_all(&dir)?;
+                dir
+            }
+            None => dir.to_path_buf(),
+        };
+
+        // Members sharing an extension are told apart by their position
+        // among them, so the names within a set never clash
+        let offsets: Vec<usize> = members
+            .iter()
+            .enumerate()
+            .map(|(index, member)| {
+                members[..index]
+                    .iter()
+                    .filter(|other| other.extension == member.extension)
+                    .count()
+            })
+            .collect();
+
+        let (collision, targets) = (0..)
+            .map(|n| {
+                let targets = members
+                    .iter()
+                    .zip(&offsets)
+                    .map(|(member, offset)| {
+                        let counter = Some(n + offset).filter(|counter| *counter > 0);
+                        let filename = build_file_name(
+                            &sanitizer,
+                            &base,
+                            counter,
+                            member.extension.as_deref(),
+                        );
+                        dir.join(filename)
+                    })
+                    .collect::<Vec<_>>();
+                (n, targets)
+            })
+            .find(|(_, targets)| targets.iter().all(|path| !path.exists()))
             .ok_or(RenameError::NoAvailableFilename)?;
 
-        // Rename file
-        std::fs::rename(path, &new_path)?;
+        let sources: Vec<&Path> = members.iter().map(|member| member.path.as_path()).collect();
+
+        // Rename files
+        move_all(&sources, &targets)?;
+
+        if let Some((mut store, key, value)) = sequence {
+            store.commit(&key, value)?;
+        }
+
+        for (original, target) in sources.iter().zip(&targets) {
+            log::info!(
+                "File renamed successfully to: {} (date {} from {})",
+                target.display(),
+                date.date_naive(),
+                source.as_str()
+            );
+            self.record(Decision {
+                path: Some(target),
+                rule: naming.rule,
+                source: Some(source),
+                date: Some(date.date_naive()),
+                collision: Some(collision).filter(|n| *n > 0),
+                ..Decision::new(original, Outcome::Renamed)
+            });
+        }
+
+        Ok(targets)
+    }
+
+    /// Whether `path` is one of the files the app writes as it runs, which
+    /// would be renamed every time they change if kept in `WATCH_PATH`.
+    fn is_written_by_app(&self, path: &Path) -> bool {
+        let path = path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+
+        [
+            self.config.sequence_path(),
+            self.config.state_path(),
+            self.config.audit_path(),
+            logging::log_path(),
+        ]
+        .iter()
+        .any(|file| {
+            let file = path::absolute(file).unwrap_or_else(|_| file.clone());
+            // Saved through a temporary copy, locked, or rotated
+            path == file.with_extension("tmp")
+                || path == file.with_extension("lock")
+                || is_rotation_of(&path, &file)
+        })
+    }
+
+    /// The folder `destination` stands for, relative to `WATCH_PATH`. Each
+    /// folder name is sanitized like a file name, and `.` and `..` are left
+    /// out, so only a rule whose `template` is an absolute path can move files
+    /// out of `WATCH_PATH`.
+    fn destination_dir(
+        &self,
+        template: &str,
+        destination: &str,
+        sanitizer: &Sanitizer,
+    ) -> Result<PathBuf, RenameError> {
+        let watch_path = Path::new(&self.config.watch_path);
+
+        let mut folder = PathBuf::new();
+
+        for component in Path::new(destination.trim()).components() {
+            match component {
+                Component::CurDir | Component::ParentDir => {}
+                Component::Normal(name) => {
+                    folder.push(sanitizer.sanitize(&name.to_string_lossy(), ""))
+                }
+                other => folder.push(other),
+            }
+        }
+
+        let dir = watch_path.join(folder);
+
+        if !Path::new(template).is_absolute() && !dir.starts_with(watch_path) {
+            return Err(RenameError::OutsideWatchPath(dir));
+        }
+
+        Ok(dir)
+    }
+
+    /// Adds a decision to the audit log and, unless the file was left
+    /// alone, to the state store. A failure to record is logged and does not
+    /// undo the rename.
+    fn record(&self, decision: Decision) {
+        let time = self.config.timezone.now();
+
+        let event = AuditEvent {
+            time,
+            profile: self.config.watch_path.clone(),
+            file: decision.file.to_path_buf(),
+            path: decision.path.map(Path::to_path_buf),
+            rule: decision.rule.map(String::from),
+            source: decision.source,
+            date: decision.date,
+            collision: decision.collision,
+            outcome: decision.outcome,
+            error: decision.error.clone(),
+        };
+
+        let audit = AuditLog::new(
+            &self.config.audit_path(),
+            self.config.audit_max_size_mb * 1024 * 1024,
+            TimeDelta::days(self.config.audit_max_age_days.into()),
+            self.config.audit_keep_files,
+        );
+
+        if let Err(err) = audit.write(&event) {
+            log::warn!("Failed to write audit log: {}", err);
+        }
+
+        if decision.outcome == Outcome::Skipped {
+            return;
+        }
+
+        let path = decision.path.unwrap_or(decision.file);
+        let (hash, size) = fingerprint(path).ok().unzip();
+
+        let record = Record {
+            time,
+            original: decision.file.to_path_buf(),
+            path: path.to_path_buf(),
+            hash,
+            size,
+            source: decision.source,
+            outcome: decision.outcome,
+            error: decision.error,
+        };
+
+        if let Err(err) = StateStore::new(&self.config.state_path()).append(&record) {
+            log::warn!("Failed to record {}: {}", decision.file.display(), err);
+        }
+    }
+
+    fn formatter(&self) -> DateFormatter {
+        DateFormatter::new(
+            self.config.locale,
+            self.config.name_case,
+            self.config.fiscal_year_start,
+        )
+    }
+
+    /// Takes the date from the first configured source that any of the
+    /// documents has within the configured bounds, and returns it with the
+    /// source it came from. Period rules only apply to the processing date,
+    /// since document dates are already the real date of the invoice.
+    fn resolve_date(
+        &self,
+        documents: &[&Document],
+        sources: &[DateSource],
+    ) -> (DateTime<FixedOffset>, DateSource) {
+        let timezone = &self.config.timezone;
+
+        let period = PeriodRules::new(
+            self.config.period_grace_days,
+            self.config.period_offset_months,
+            self.config.period_business_days,
+            &self.config.holidays,
+        );
+
+        let found = sources.iter().find_map(|source| {
+            documents
+                .iter()
+                .filter_map(|document| document.date(*source, timezone))
+                .find(|date| self.within_bounds(*source, date))
+                .map(|date| (date, *source))
+        });
+
+        match found {
+            Some((date, DateSource::Now)) => (period.apply(date), DateSource::Now),
+            Some(found) => found,
+            None => {
+                log::info!("No configured date source matched, using the current date");
+                (period.apply(timezone.now()), DateSource::Now)
+            }
+        }
+    }
+
+    /// Rejects document dates in the future or older than
+    /// `max_date_age_months` when configured, so the next source is tried.
+    fn within_bounds(&self, source: DateSource, date: &DateTime<FixedOffset>) -> bool {
+        if source == DateSource::Now {
+            return true;
+        }
+
+        let date = date.date_naive();
+        let today = self.config.timezone.now().date_naive();
+
+        if self.config.reject_future_dates && date > today {
+            log::info!(
+                "Ignoring date {} from {}: it is in the future",
+                date,
+                source.as_str()
+            );
+            return false;
+        }
+
+        if let Some(months) = self.config.max_date_age_months {
+            let oldest = today
+                .checked_sub_months(Months::new(months))
+                .unwrap_or(NaiveDate::MIN);
+
+            if date < oldest {
+                log::info!(
+                    "Ignoring date {} from {}: older than {} months",
+                    date,
+                    source.as_str(),
+                    months
+                );
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Checks the detected content type against `allowed_types`. An empty
+    /// list allows everything.
+    fn check_allowed(&self, detected: Option<FileType>) -> Result<(), String> {
+        let allowed = &self.config.allowed_types;
+
+        if allowed.is_empty() {
+            return Ok(());
+        }
+
+        match detected {
+            Some(file_type) if allowed.contains(&file_type) => Ok(()),
+            Some(file_type) => Err(format!(
+                "content is {} which is not an allowed type",
+                file_type.extension()
+            )),
+            None => Err("content type could not be identified".to_string()),
+        }
+    }
+
+    /// Keeps the current extension unless inference is enabled and the
+    /// file's content says it is missing, generic like `.bin`, or the one of
+    /// another detected type, like a `.pdf` that is an HTML page. Formats
+    /// that are not detected, such as `.svg` over XML or `.ai` over PDF,
+    /// keep their extension.
+    fn resolve_extension(
+        &self,
+        detected: Option<FileType>,
+        extension: Option<&str>,
+    ) -> Option<String> {
+        let (true, Some(file_type)) = (self.config.infer_extension, detected) else {
+            return extension.map(String::from);
+        };
+
+        match extension {
+            None => {
+                log::info!("Inferred extension .{} from content", file_type.extension());
+                Some(file_type.extension().to_string())
+            }
+            Some(extension)
+                if is_generic_extension(extension)
+                    || FileType::from_extension(extension)
+                        .is_some_and(|claimed| claimed != file_type) =>
+            {
+                log::info!(
+                    "Extension .{} does not match content, using .{}",
+                    extension,
+                    file_type.extension()
+                );
+                Some(file_type.extension().to_string())
+            }
+            Some(extension) => Some(extension.to_string()),
+        }
+    }
+}
+
+/// Fields of every member, the first member with a value winning.
+fn merge_fields(members: &[Candidate]) -> Fields {
+    let mut fields = Fields::new();
+
+    for member in members {
+        for (name, value) in member.document.fields() {
+            fields.entry(name).or_insert(value);
+        }
+    }
+
+    fields
+}
+
+/// Whether a file is labelled as `file_type` by its extension, or has none
+/// or a generic one. Office documents are zip files underneath and text
+/// files can look like emails, and they must never be taken apart.
+fn is_labelled_as(file_type: FileType, extension: Option<&str>) -> bool {
+    extension.is_none_or(|extension| {
+        extension.eq_ignore_ascii_case(file_type.extension()) || is_generic_extension(extension)
+    })
+}
+
+/// Renames every `sources[i]` to `targets[i]`, undoing the ones already
+/// done if any of them fails.
+fn move_all(sources: &[&Path], targets: &[PathBuf]) -> io::Result<()> {
+    for (index, (source, target)) in sources.iter().zip(targets).enumerate() {
+        if let Err(err) = fs::rename(source, target) {
+            for (source, target) in sources[..index].iter().zip(targets).rev() {
+                if let Err(err) = fs::rename(target, source) {
+                    log::error!("Failed to restore {}: {}", source.display(), err);
+                }
+            }
+
+            return Err(err);
+        }
+    }
+
+    Ok(())
+}
+
+fn build_file_name(
+    sanitizer: &Sanitizer,
+    base: &str,
+    counter: Option<usize>,
+    extension: Option<&str>,
+) -> String {
+    let mut suffix = match counter {
+        Some(n) => format!(" ({})", n),
+        None => String::new(),
+    };
+
+    if let Some(extension) = extension {
+        suffix.push('.');
+        suffix.push_str(extension);
+    }
+
+    format!("{}{}", sanitizer.sanitize(base, &suffix), suffix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unpacks_only_files_labelled_as_archives() {
+        assert!(is_labelled_as(FileType::Zip, Some("zip")));
+        assert!(is_labelled_as(FileType::Zip, Some("ZIP")));
+        assert!(is_labelled_as(FileType::Zip, Some("bin")));
+        assert!(is_labelled_as(FileType::Zip, None));
+
+        for extension in ["xlsx", "docx", "odt", "epub", "jar", "apk"] {
+            assert!(
+                !is_labelled_as(FileType::Zip, Some(extension)),
+                "{}",
+                extension
+            );
+        }
+
+        assert!(is_labelled_as(FileType::Eml, Some("eml")));
+        assert!(!is_labelled_as(FileType::Eml, Some("txt")));
+    }
+
+    #[test]
+    fn keeps_destinations_inside_the_watch_path() {
+        let vars = [
+            ("WATCH_PATH", "/watch"),
+            ("FILE_FORMAT", "%Y-%m-%d"),
+            ("DELAY_SECONDS", "0"),
+        ];
+        let config =
+            AppConfig::load_from(vars.map(|(key, value)| (key.to_string(), value.to_string())))
+                .unwrap();
+        let watch_path = Path::new("/watch");
+        let renamer = FileRenamer::new(config);
+        let sanitizer = Sanitizer::new("_", 255);
 
-        log::info!("File renamed successfully to: {}", new_path.display());
+        let dir = |destination| renamer.destination_dir("{vendor}", destination, &sanitizer);
 
-        Ok(new_path)
+        assert_eq!(
+            dir("ACME_ S.A.S./2025").unwrap(),
+            watch_path.join("ACME_ S.A.S/2025")
+        );
+        assert_eq!(dir("../../etc").unwrap(), watch_path.join("etc"));
+        assert_eq!(dir("./CON/.").unwrap(), watch_path.join("CON_"));
+        assert!(matches!(dir("/etc"), Err(RenameError::OutsideWatchPath(_))));
     }
 }
@@ -0,0 +1,176 @@
+use crate::file::sniffer::FileType;
+use crate::file::template::Fields;
+use crate::source::DateSource;
+use crate::toml_file::{self, TomlFileError, deserialize_regex};
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Deserializer};
+use std::fs;
+use std::path::Path;
+
+/// Ordered naming rules, read from `RULES_FILE`. The first rule whose
+/// conditions all hold decides what happens to a file.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct Rules {
+    #[serde(default, rename = "rule")]
+    rules: Vec<Rule>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    pub name: Option<String>,
+
+    /// Wildcard pattern (`*`, `?`) for the file name, ignoring case.
+    #[serde(default, deserialize_with = "deserialize_glob")]
+    glob: Option<Regex>,
+    /// Regex searched in the file name.
+    #[serde(default, deserialize_with = "deserialize_regex")]
+    pattern: Option<Regex>,
+    #[serde(default)]
+    extensions: Vec<String>,
+    #[serde(default)]
+    types: Vec<FileType>,
+    min_size: Option<u64>,
+    max_size: Option<u64>,
+    /// Text in the supplier NIT or name read from the document.
+    supplier: Option<String>,
+    /// Vendor name from `VENDORS_FILE`, ignoring case.
+    vendor: Option<String>,
+
+    /// Replaces `FILE_FORMAT`.
+    pub format: Option<String>,
+    /// Folder the file is moved to, relative to `WATCH_PATH` unless
+    /// absolute. Fields can be used in it.
+    pub destination: Option<String>,
+    /// Replaces `DATE_SOURCES`.
+    pub date_sources: Option<Vec<DateSource>>,
+    /// Leaves the file as it is.
+    #[serde(default)]
+    pub skip: bool,
+}
+
+/// What a rule gets to look at.
+pub struct Subject<'a> {
+    pub path: &'a Path,
+    pub extension: Option<&'a str>,
+    pub file_type: Option<FileType>,
+    pub fields: &'a Fields,
+}
+
+impl Rules {
+    pub fn load(path: &Path) -> Result<Self, TomlFileError> {
+        toml_file::load(path, "rules")
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Rule> {
+        self.rules.iter()
+    }
+
+    pub fn find(&self, subject: &Subject) -> Option<&Rule> {
+        self.rules.iter().find(|rule| rule.matches(subject))
+    }
+}
+
+impl Rule {
+    pub fn label(&self) -> &str {
+        self.name.as_deref().unwrap_or("unnamed")
+    }
+
+    fn matches(&self, subject: &Subject) -> bool {
+        let name = subject
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default();
+
+        if self.glob.as_ref().is_some_and(|glob| !glob.is_match(&name)) {
+            return false;
+        }
+
+        if self
+            .pattern
+            .as_ref()
+            .is_some_and(|pattern| !pattern.is_match(&name))
+        {
+            return false;
+        }
+
+        if !self.extensions.is_empty()
+            && !subject.extension.is_some_and(|extension| {
+                self.extensions
+                    .iter()
+                    .any(|allowed| allowed.eq_ignore_ascii_case(extension))
+            })
+        {
+            return false;
+        }
+
+        if !self.types.is_empty()
+            && !subject
+                .file_type
+                .is_some_and(|file_type| self.types.contains(&file_type))
+        {
+            return false;
+        }
+
+        if self.min_size.is_some() || self.max_size.is_some() {
+            let Ok(size) = fs::metadata(subject.path).map(|metadata| metadata.len()) else {
+                return false;
+            };
+
+            if self.min_size.is_some_and(|min| size < min)
+                || self.max_size.is_some_and(|max| size > max)
+            {
+                return false;
+            }
+        }
+
+        if let Some(supplier) = &self.supplier {
+            let supplier = supplier.to_lowercase();
+
+            let found = ["supplier_nit", "supplier_name"].iter().any(|field| {
+                subject
+                    .fields
+                    .get(*field)
+                    .is_some_and(|value| value.to_lowercase().contains(&supplier))
+            });
+
+            if !found {
+                return false;
+            }
+        }
+
+        if let Some(vendor) = &self.vendor
+            && !subject
+                .fields
+                .get("vendor")
+                .is_some_and(|value| value.eq_ignore_ascii_case(vendor))
+        {
+            return false;
+        }
+
+        true
+    }
+}
+
+fn deserialize_glob<'de, D>(deserializer: D) -> Result<Option<Regex>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let glob = String::deserialize(deserializer)?;
+
+    let pattern = glob
+        .chars()
+        .map(|c| match c {
+            '*' => ".*".to_string(),
+            '?' => ".".to_string(),
+            c => regex::escape(&c.to_string()),
+        })
+        .collect::<String>();
+
+    RegexBuilder::new(&format!("^{}$", pattern))
+        .case_insensitive(true)
+        .build()
+        .map(Some)
+        .map_err(|err| serde::de::Error::custom(format!("invalid glob `{}`: {}", glob, err)))
+}
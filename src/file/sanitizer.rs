@@ -0,0 +1,168 @@
+const RESERVED_CHARACTERS: &[char] = &['<', '>', ':', '"', '/', '\\', '|', '?', '*'];
+
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+const FALLBACK_NAME: &str = "unnamed";
+
+/// Makes generated names safe to use as a single path component on Windows
+/// and on SMB shares.
+pub struct Sanitizer<'a> {
+    replacement: &'a str,
+    max_length: usize,
+}
+
+impl<'a> Sanitizer<'a> {
+    pub fn new(replacement: &'a str, max_length: usize) -> Self {
+        Self {
+            replacement,
+            max_length,
+        }
+    }
+
+    /// Sanitizes `base` so that, once `suffix` (counter and extension) is
+    /// appended, the result fits in `max_length` bytes.
+    pub fn sanitize(&self, base: &str, suffix: &str) -> String {
+        let mut name = self.replace_reserved(base);
+
+        let budget = self.max_length.saturating_sub(suffix.len());
+        truncate(&mut name, budget);
+
+        let mut name = trim(&name).to_string();
+
+        if name.is_empty() {
+            name = FALLBACK_NAME.to_string();
+            truncate(&mut name, budget);
+        }
+
+        if is_reserved_name(&name) {
+            let marker = if self.replacement.is_empty() {
+                "_"
+            } else {
+                self.replacement
+            };
+            truncate(&mut name, budget.saturating_sub(marker.len()));
+            name.push_str(marker);
+        }
+
+        name
+    }
+
+    /// Whether `text` can appear in a file name, as `NAME_REPLACEMENT` must.
+    pub fn is_allowed(text: &str) -> bool {
+        !text.chars().any(is_reserved_character)
+    }
+
+    /// Replaces characters that can't appear in a file name.
+    pub fn replace_reserved(&self, text: &str) -> String {
+        let mut result = String::with_capacity(text.len());
+
+        for c in text.chars() {
+            if is_reserved_character(c) {
+                result.push_str(self.replacement);
+            } else {
+                result.push(c);
+            }
+        }
+
+        result
+    }
+}
+
+fn is_reserved_character(c: char) -> bool {
+    RESERVED_CHARACTERS.contains(&c) || c.is_control()
+}
+
+/// Windows forbids device names even with an extension, so `NUL` and
+/// `nul.2025` are both rejected.
+fn is_reserved_name(name: &str) -> bool {
+    let device = name.split('.').next().unwrap_or(name).trim_end();
+
+    RESERVED_NAMES
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(device))
+}
+
+fn trim(name: &str) -> &str {
+    name.trim_start_matches(' ').trim_end_matches(['.', ' '])
+}
+
+fn truncate(name: &mut String, max_length: usize) {
+    if name.len() <= max_length {
+        return;
+    }
+
+    let index = (0..=max_length)
+        .rev()
+        .find(|&index| name.is_char_boundary(index))
+        .unwrap_or(0);
+
+    name.truncate(index);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replaces_reserved_characters() {
+        let sanitizer = Sanitizer::new("_", 255);
+
+        assert_eq!(
+            sanitizer.sanitize("2025-10-19 10:30", ".pdf"),
+            "2025-10-19 10_30"
+        );
+        assert_eq!(sanitizer.sanitize("a/b\\c|d?e*f", ""), "a_b_c_d_e_f");
+        assert_eq!(sanitizer.sanitize("tab\there", ""), "tab_here");
+        assert_eq!(Sanitizer::new("", 255).sanitize("10:30", ""), "1030");
+    }
+
+    #[test]
+    fn avoids_reserved_names() {
+        let sanitizer = Sanitizer::new("_", 255);
+
+        assert_eq!(sanitizer.sanitize("con", ".pdf"), "con_");
+        assert_eq!(sanitizer.sanitize("NUL.2025", ""), "NUL.2025_");
+        assert_eq!(sanitizer.sanitize("console", ""), "console");
+        assert_eq!(Sanitizer::new("", 255).sanitize("aux", ""), "aux_");
+    }
+
+    #[test]
+    fn leaves_room_for_the_reserved_name_marker() {
+        assert_eq!(Sanitizer::new("_", 7).sanitize("NUL.2025", ".pdf"), "NU_");
+        assert_eq!(Sanitizer::new("--", 8).sanitize("con", ".pdf"), "co--");
+        assert_eq!(Sanitizer::new("_", 8).sanitize("con", ".pdf"), "con_");
+    }
+
+    #[test]
+    fn allows_only_safe_replacements() {
+        assert!(Sanitizer::is_allowed("_"));
+        assert!(Sanitizer::is_allowed(""));
+        assert!(Sanitizer::is_allowed(" - "));
+        assert!(!Sanitizer::is_allowed("/"));
+        assert!(!Sanitizer::is_allowed("a:b"));
+        assert!(!Sanitizer::is_allowed("\t"));
+    }
+
+    #[test]
+    fn trims_dots_and_spaces() {
+        let sanitizer = Sanitizer::new("_", 255);
+
+        assert_eq!(sanitizer.sanitize("  report. . ", ""), "report");
+        assert_eq!(sanitizer.sanitize(" .. ", ""), FALLBACK_NAME);
+        assert_eq!(sanitizer.sanitize("", ".pdf"), FALLBACK_NAME);
+    }
+
+    #[test]
+    fn leaves_room_for_the_suffix() {
+        let sanitizer = Sanitizer::new("_", 12);
+
+        assert_eq!(sanitizer.sanitize("2025-10-19 report", " (1).pdf"), "2025");
+        assert_eq!(sanitizer.sanitize("octubre", ".pdf"), "octubre");
+
+        // Never cuts a character in half
+        assert_eq!(Sanitizer::new("_", 5).sanitize("añoño", ""), "año");
+    }
+}
@@ -0,0 +1,101 @@
+use crate::lock_file;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use thiserror::Error;
+
+/// Field replaced by the next number of the name's sequence.
+pub const SEQUENCE_FIELD: &str = "seq";
+
+/// Held by the open store of this process. The lock file next to the store
+/// does the same across processes.
+static STORE_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Error, Debug)]
+pub enum SequenceError {
+    #[error("Failed to access sequence file {0}: {1}")]
+    IoError(String, io::Error),
+
+    #[error("Invalid sequence file {0}: {1}")]
+    ParseError(String, serde_json::Error),
+}
+
+/// Last number handed out for each sequence, kept in `SEQUENCE_FILE` so
+/// numbering carries on across restarts and after files are moved or
+/// deleted. Only one store is open at a time, so a number cannot be handed
+/// out twice between `peek` and `commit`.
+pub struct SequenceStore {
+    path: PathBuf,
+    counters: BTreeMap<String, u64>,
+    _guard: MutexGuard<'static, ()>,
+    _lock: File,
+}
+
+#[derive(Default, Deserialize, Serialize)]
+struct Counters {
+    counters: BTreeMap<String, u64>,
+}
+
+impl SequenceStore {
+    /// Reads the store at `path`. A missing file is an empty store. Waits
+    /// while the store is open elsewhere, in this process or another.
+    pub fn open(path: &Path) -> Result<Self, SequenceError> {
+        let display = path.display().to_string();
+
+        let guard = STORE_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+        let lock =
+            lock_file::lock(path).map_err(|err| SequenceError::IoError(display.clone(), err))?;
+
+        let counters = match fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str::<Counters>(&content)
+                .map_err(|err| SequenceError::ParseError(display, err))?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Counters::default(),
+            Err(err) => return Err(SequenceError::IoError(display, err)),
+        };
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            counters: counters.counters,
+            _guard: guard,
+            _lock: lock,
+        })
+    }
+
+    /// The number after the last one taken in sequence `key`. It is only
+    /// taken once `commit` is called with it.
+    pub fn peek(&self, key: &str) -> u64 {
+        self.counters.get(key).copied().unwrap_or_default() + 1
+    }
+
+    /// Records `value` as taken in sequence `key` and saves the store.
+    pub fn commit(&mut self, key: &str, value: u64) -> Result<(), SequenceError> {
+        let counter = self.counters.entry(key.to_string()).or_default();
+        *counter = (*counter).max(value);
+
+        self.save()
+            .map_err(|err| SequenceError::IoError(self.path.display().to_string(), err))
+    }
+
+    /// Writes a temporary file next to the store and renames it over the
+    /// store, so a crash never leaves it half written.
+    fn save(&self) -> io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let counters = Counters {
+            counters: self.counters.clone(),
+        };
+        let content = serde_json::to_string_pretty(&counters).map_err(io::Error::other)?;
+
+        let temp = self.path.with_extension("tmp");
+        let mut file = fs::File::create(&temp)?;
+        file.write_all(content.as_bytes())?;
+        file.sync_all()?;
+
+        fs::rename(&temp, &self.path)
+    }
+}
@@ -0,0 +1,205 @@
+use serde::Deserialize;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+const SNIFF_LENGTH: u64 = 512;
+
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// Extensions that say nothing about the content, such as the ones given to
+/// downloads and temporary copies.
+const GENERIC_EXTENSIONS: &[&str] = &["bin", "dat", "tmp", "file"];
+
+/// Headers a saved email can start with, lowercased.
+const EMAIL_HEADERS: &[&[u8]] = &[
+    b"received:",
+    b"return-path:",
+    b"delivered-to:",
+    b"from:",
+    b"date:",
+    b"to:",
+    b"subject:",
+    b"message-id:",
+    b"mime-version:",
+    b"x-mozilla-status:",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileType {
+    Pdf,
+    Png,
+    #[serde(alias = "jpg")]
+    Jpeg,
+    #[serde(alias = "heif")]
+    Heic,
+    Zip,
+    Xml,
+    Html,
+    Eml,
+}
+
+impl FileType {
+    const ALL: [FileType; 8] = [
+        FileType::Pdf,
+        FileType::Png,
+        FileType::Jpeg,
+        FileType::Heic,
+        FileType::Zip,
+        FileType::Xml,
+        FileType::Html,
+        FileType::Eml,
+    ];
+
+    /// Detects the type of the file from its leading bytes.
+    pub fn detect(path: &Path) -> io::Result<Option<Self>> {
+        let mut buffer = Vec::new();
+        File::open(path)?
+            .take(SNIFF_LENGTH)
+            .read_to_end(&mut buffer)?;
+
+        Ok(Self::from_bytes(&buffer))
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.starts_with(b"%PDF-") {
+            return Some(FileType::Pdf);
+        }
+
+        if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+            return Some(FileType::Png);
+        }
+
+        if bytes.starts_with(b"\xFF\xD8\xFF") {
+            return Some(FileType::Jpeg);
+        }
+
+        if is_heif(bytes) {
+            return Some(FileType::Heic);
+        }
+
+        if bytes.starts_with(b"PK\x03\x04") || bytes.starts_with(b"PK\x05\x06") {
+            return Some(FileType::Zip);
+        }
+
+        let text = bytes.strip_prefix(UTF8_BOM).unwrap_or(bytes);
+        let text = &text[text.iter().take_while(|b| b.is_ascii_whitespace()).count()..];
+
+        if text.starts_with(b"<?xml") {
+            return Some(FileType::Xml);
+        }
+
+        let head = text[..text.len().min(14)].to_ascii_lowercase();
+
+        if head.starts_with(b"<!doctype html") || head.starts_with(b"<html") {
+            return Some(FileType::Html);
+        }
+
+        let head = text[..text.len().min(20)].to_ascii_lowercase();
+
+        if EMAIL_HEADERS.iter().any(|header| head.starts_with(header)) {
+            return Some(FileType::Eml);
+        }
+
+        None
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            FileType::Pdf => "pdf",
+            FileType::Png => "png",
+            FileType::Jpeg => "jpg",
+            FileType::Heic => "heic",
+            FileType::Zip => "zip",
+            FileType::Xml => "xml",
+            FileType::Html => "html",
+            FileType::Eml => "eml",
+        }
+    }
+
+    /// Whether `extension` is an acceptable label for this type. Zip covers
+    /// the office and archive formats that are zip containers underneath.
+    pub fn matches_extension(&self, extension: &str) -> bool {
+        let extension = extension.to_lowercase();
+
+        let accepted: &[&str] = match self {
+            FileType::Pdf => &["pdf"],
+            FileType::Png => &["png"],
+            FileType::Jpeg => &["jpg", "jpeg", "jpe", "jfif"],
+            FileType::Heic => &["heic", "heif", "hif"],
+            FileType::Zip => &[
+                "zip", "docx", "xlsx", "pptx", "odt", "ods", "odp", "jar", "epub",
+            ],
+            FileType::Xml => &["xml"],
+            FileType::Html => &["html", "htm"],
+            FileType::Eml => &["eml"],
+        };
+
+        accepted.contains(&extension.as_str())
+    }
+
+    /// The type `extension` is an acceptable label for, if it is one of the
+    /// types that can be detected.
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|file_type| file_type.matches_extension(extension))
+    }
+}
+
+/// Whether `extension` says nothing about the content, like `.bin`.
+pub fn is_generic_extension(extension: &str) -> bool {
+    GENERIC_EXTENSIONS
+        .iter()
+        .any(|generic| generic.eq_ignore_ascii_case(extension))
+}
+
+/// HEIF images start with an `ftyp` box naming one of the image brands.
+fn is_heif(bytes: &[u8]) -> bool {
+    const BRANDS: &[&[u8]] = &[b"heic", b"heix", b"heim", b"heis", b"mif1", b"msf1"];
+
+    bytes.get(4..8) == Some(b"ftyp")
+        && bytes
+            .get(8..12)
+            .is_some_and(|brand| BRANDS.contains(&brand))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_content() {
+        assert_eq!(FileType::from_bytes(b"%PDF-1.7"), Some(FileType::Pdf));
+        assert_eq!(FileType::from_bytes(b"PK\x03\x04"), Some(FileType::Zip));
+        assert_eq!(
+            FileType::from_bytes(b"\xEF\xBB\xBF  <?xml version"),
+            Some(FileType::Xml)
+        );
+        assert_eq!(
+            FileType::from_bytes(b"<!DOCTYPE html>"),
+            Some(FileType::Html)
+        );
+        assert_eq!(FileType::from_bytes(b"Date: Mon"), Some(FileType::Eml));
+        assert_eq!(FileType::from_bytes(b"plain text"), None);
+    }
+
+    #[test]
+    fn maps_extensions_to_types() {
+        assert_eq!(FileType::from_extension("PDF"), Some(FileType::Pdf));
+        assert_eq!(FileType::from_extension("jpeg"), Some(FileType::Jpeg));
+        assert_eq!(FileType::from_extension("xlsx"), Some(FileType::Zip));
+        assert_eq!(FileType::from_extension("svg"), None);
+        assert_eq!(FileType::from_extension("ai"), None);
+        assert_eq!(FileType::from_extension("txt"), None);
+    }
+
+    #[test]
+    fn recognises_generic_extensions() {
+        assert!(is_generic_extension("bin"));
+        assert!(is_generic_extension("TMP"));
+        assert!(!is_generic_extension("pdf"));
+        assert!(!is_generic_extension("kmz"));
+    }
+}
@@ -0,0 +1,128 @@
+use crate::file::sanitizer::Sanitizer;
+use std::collections::HashMap;
+
+/// Values extracted from a document, available as `{name}` in `FILE_FORMAT`.
+pub type Fields = HashMap<String, String>;
+
+/// Fields that can appear in `FILE_FORMAT`. A field with no value for the
+/// current file renders as an empty string.
+pub const FIELD_NAMES: &[&str] = &[
+    "invoice_number",
+    "supplier_nit",
+    "supplier_name",
+    "total",
+    "currency",
+    "email_from",
+    "sender_domain",
+    "email_subject",
+    "vendor",
+];
+
+/// Replaces every `{name}` in `text` for which `replace` returns a value,
+/// leaving anything else, such as regex quantifiers, untouched.
+pub fn replace_placeholders(text: &str, mut replace: impl FnMut(&str) -> Option<String>) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('{') {
+        let (before, after) = rest.split_at(start);
+        result.push_str(before);
+
+        let replacement = after[1..]
+            .find('}')
+            .and_then(|end| replace(&after[1..end + 1]).map(|value| (value, end)));
+
+        match replacement {
+            Some((value, end)) => {
+                result.push_str(&value);
+                rest = &after[end + 2..];
+            }
+            None => {
+                result.push('{');
+                rest = &after[1..];
+            }
+        }
+    }
+
+    result.push_str(rest);
+    result
+}
+
+/// Fills the fields in `format`, escaping `%` so values are not read as
+/// date specifiers.
+pub fn render_fields(format: &str, fields: &Fields) -> String {
+    render(format, fields, str::to_string)
+}
+
+/// Fills the fields in a destination folder. Values come from documents and
+/// must not add or leave folders: characters that can't be in a name, path
+/// separators included, are replaced, and values made only of dots are left
+/// out.
+pub fn render_folder_fields(format: &str, fields: &Fields, sanitizer: &Sanitizer) -> String {
+    render(format, fields, |value| {
+        let value = sanitizer.replace_reserved(value);
+
+        if value.chars().all(|c| c == '.') {
+            String::new()
+        } else {
+            value
+        }
+    })
+}
+
+fn render(format: &str, fields: &Fields, clean: impl Fn(&str) -> String) -> String {
+    replace_placeholders(format, |name| {
+        if !FIELD_NAMES.contains(&name) {
+            return None;
+        }
+
+        let value = fields.get(name).map(String::as_str).unwrap_or_default();
+        Some(clean(value).replace('%', "%%"))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(values: &[(&str, &str)]) -> Fields {
+        values
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn renders_fields() {
+        let fields = fields(&[("vendor", "ACME"), ("total", "100%")]);
+
+        assert_eq!(
+            render_fields("%Y {vendor} {total} {missing} {currency}", &fields),
+            "%Y ACME 100%% {missing} "
+        );
+    }
+
+    #[test]
+    fn keeps_folder_fields_in_their_folder() {
+        let sanitizer = Sanitizer::new("_", 255);
+        let fields = fields(&[
+            ("supplier_name", "..\\..\\Windows"),
+            ("email_subject", "C:\\Users/report: 10/2025"),
+            ("invoice_number", ".."),
+            ("vendor", "ACME S.A.S."),
+        ]);
+
+        assert_eq!(
+            render_folder_fields("{supplier_name}/{invoice_number}", &fields, &sanitizer),
+            ".._.._Windows/"
+        );
+        assert_eq!(
+            render_folder_fields("Mail/{email_subject}", &fields, &sanitizer),
+            "Mail/C__Users_report_ 10_2025"
+        );
+        assert_eq!(
+            render_folder_fields("{vendor}/%Y", &fields, &sanitizer),
+            "ACME S.A.S./%Y"
+        );
+    }
+}
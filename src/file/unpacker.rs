@@ -0,0 +1,322 @@
+use crate::source::email::attachments;
+use mailparse::{MailParseError, parse_mail};
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use zip::ZipArchive;
+use zip::result::ZipError;
+
+/// Prefix of the folders files are extracted into before being renamed.
+/// Anything inside them is ignored by the renamer.
+pub const STAGING_PREFIX: &str = ".autodate-unpack-";
+
+#[derive(Error, Debug)]
+pub enum UnpackError {
+    #[error("I/O error")]
+    IoError(#[from] io::Error),
+
+    #[error("Invalid archive: {0}")]
+    InvalidArchive(#[from] ZipError),
+
+    #[error("Invalid email: {0}")]
+    InvalidEmail(#[from] MailParseError),
+
+    #[error("Archive has more than {0} entries")]
+    TooManyEntries(usize),
+
+    #[error("Archive expands to more than {0} bytes")]
+    TooLarge(u64),
+}
+
+pub struct Unpacker {
+    max_entries: usize,
+    max_size: u64,
+}
+
+impl Unpacker {
+    pub fn new(max_entries: usize, max_size: u64) -> Self {
+        Self {
+            max_entries,
+            max_size,
+        }
+    }
+
+    /// Extracts the files in `archive` into a staging folder next to it and
+    /// returns their paths. Folder structure inside the archive is dropped,
+    /// and entries that would escape the staging folder are skipped.
+    pub fn unpack(&self, archive: &Path) -> Result<(PathBuf, Vec<PathBuf>), UnpackError> {
+        let mut zip = ZipArchive::new(File::open(archive)?)?;
+
+        if zip.len() > self.max_entries {
+            return Err(UnpackError::TooManyEntries(self.max_entries));
+        }
+
+        stage(archive, |staging| self.extract(&mut zip, staging))
+    }
+
+    /// Saves the attachments of the email at `email` into a staging folder
+    /// next to it and returns their paths, with the same limits as archives.
+    /// An email file larger than the size limit is not read at all.
+    pub fn unpack_email(&self, email: &Path) -> Result<(PathBuf, Vec<PathBuf>), UnpackError> {
+        if fs::metadata(email)?.len() > self.max_size {
+            return Err(UnpackError::TooLarge(self.max_size));
+        }
+
+        let data = fs::read(email)?;
+        let mail = parse_mail(&data)?;
+        let attachments = attachments(&mail);
+
+        if attachments.len() > self.max_entries {
+            return Err(UnpackError::TooManyEntries(self.max_entries));
+        }
+
+        stage(email, |staging| {
+            let mut files = Vec::new();
+            let mut remaining = self.max_size;
+
+            for (name, part) in attachments {
+                let body = part.get_body_raw()?;
+
+                if body.len() as u64 > remaining {
+                    return Err(UnpackError::TooLarge(self.max_size));
+                }
+
+                let target = unique_path(staging, Path::new(&name));
+                fs::write(&target, &body)?;
+
+                remaining -= body.len() as u64;
+                files.push(target);
+            }
+
+            Ok(files)
+        })
+    }
+
+    fn extract(
+        &self,
+        zip: &mut ZipArchive<File>,
+        staging: &Path,
+    ) -> Result<Vec<PathBuf>, UnpackError> {
+        let mut files = Vec::new();
+        let mut remaining = self.max_size;
+
+        for index in 0..zip.len() {
+            let mut entry = zip.by_index(index)?;
+
+            if entry.is_dir() || entry.is_symlink() {
+                continue;
+            }
+
+            let Some(name) = entry
+                .enclosed_name()
+                .and_then(|path| path.file_name().map(PathBuf::from))
+            else {
+                log::warn!("Skipping unsafe archive entry: {}", entry.name());
+                continue;
+            };
+
+            if entry.size() > remaining {
+                return Err(UnpackError::TooLarge(self.max_size));
+            }
+
+            let target = unique_path(staging, &name);
+            let mut output = File::create(&target)?;
+
+            // Declared sizes can lie, so the limit is enforced on the
+            // decompressed bytes as well
+            let written = io::copy(&mut (&mut entry).take(remaining + 1), &mut output)?;
+
+            if written > remaining {
+                return Err(UnpackError::TooLarge(self.max_size));
+            }
+
+            remaining -= written;
+            files.push(target);
+        }
+
+        Ok(files)
+    }
+}
+
+/// Removes a staging folder and whatever is left in it.
+pub fn remove_staging(staging: &Path) {
+    if let Err(err) = fs::remove_dir_all(staging) {
+        log::warn!(
+            "Failed to remove staging folder {}: {}",
+            staging.display(),
+            err
+        );
+    }
+}
+
+pub fn is_staging(path: &Path) -> bool {
+    path.components().any(|component| {
+        component
+            .as_os_str()
+            .to_string_lossy()
+            .starts_with(STAGING_PREFIX)
+    })
+}
+
+/// Runs `extract` on a new staging folder for `archive`, removing the
+/// folder again if it fails.
+fn stage(
+    archive: &Path,
+    extract: impl FnOnce(&Path) -> Result<Vec<PathBuf>, UnpackError>,
+) -> Result<(PathBuf, Vec<PathBuf>), UnpackError> {
+    let staging = staging_dir(archive)?;
+    fs::create_dir_all(&staging)?;
+
+    match extract(&staging) {
+        Ok(files) => Ok((staging, files)),
+        Err(err) => {
+            remove_staging(&staging);
+            Err(err)
+        }
+    }
+}
+
+fn staging_dir(archive: &Path) -> io::Result<PathBuf> {
+    let parent = archive
+        .parent()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "File has no parent"))?;
+
+    let stem = archive
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    Ok(parent.join(format!("{}{}-{}", STAGING_PREFIX, stem, std::process::id())))
+}
+
+fn unique_path(dir: &Path, name: &Path) -> PathBuf {
+    let path = dir.join(name);
+
+    if !path.exists() {
+        return path;
+    }
+
+    let name = name.to_string_lossy();
+
+    (1..)
+        .map(|n| dir.join(format!("{}-{}", n, name)))
+        .find(|path| !path.exists())
+        .expect("Infinite iterator always yields a path")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::io::Write;
+    use zip::ZipWriter;
+    use zip::write::SimpleFileOptions;
+
+    const EMAIL: &str = "Date: Mon, 3 Nov 2025 10:00:00 +0100\r\n\
+        From: billing@acme.com\r\n\
+        Content-Type: multipart/mixed; boundary=\"b\"\r\n\
+        \r\n\
+        --b\r\n\
+        Content-Type: text/plain\r\n\
+        \r\n\
+        See attached\r\n\
+        --b\r\n\
+        Content-Type: application/pdf; name=\"invoice.pdf\"\r\n\
+        Content-Disposition: attachment; filename=\"invoice.pdf\"\r\n\
+        \r\n\
+        %PDF-1.7\r\n\
+        --b--\r\n";
+
+    fn dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("autodate-unpacker-{}", name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_zip(path: &Path, entries: &[(&str, &[u8])]) {
+        let mut zip = ZipWriter::new(File::create(path).unwrap());
+
+        for (name, content) in entries {
+            zip.start_file(*name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(content).unwrap();
+        }
+
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn flattens_entries_and_skips_those_escaping_the_folder() {
+        let dir = dir("slip");
+        let archive = dir.join("bundle.zip");
+        write_zip(
+            &archive,
+            &[
+                ("nested/invoice.pdf", b"%PDF-1.7"),
+                ("../escaped.pdf", b"%PDF-1.7"),
+                ("nested/../../escaped.xml", b"<?xml"),
+                ("invoice.pdf", b"%PDF-1.4"),
+            ],
+        );
+
+        let (staging, files) = Unpacker::new(10, 1024).unpack(&archive).unwrap();
+
+        assert_eq!(
+            files,
+            vec![staging.join("invoice.pdf"), staging.join("1-invoice.pdf")]
+        );
+        assert!(!dir.join("escaped.pdf").exists());
+        assert!(!dir.join("escaped.xml").exists());
+        remove_staging(&staging);
+    }
+
+    #[test]
+    fn stops_at_the_size_and_entry_limits() {
+        let dir = dir("limits");
+        let archive = dir.join("bundle.zip");
+        write_zip(
+            &archive,
+            &[("a.pdf", &[b'a'; 600]), ("b.pdf", &[b'b'; 600])],
+        );
+
+        let result = Unpacker::new(10, 1000).unpack(&archive);
+        assert!(matches!(result, Err(UnpackError::TooLarge(1000))));
+
+        let result = Unpacker::new(1, 10_000).unpack(&archive);
+        assert!(matches!(result, Err(UnpackError::TooManyEntries(1))));
+
+        // The staging folder of the failed attempt is removed
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        let (staging, files) = Unpacker::new(2, 1200).unpack(&archive).unwrap();
+        assert_eq!(files.len(), 2);
+        remove_staging(&staging);
+    }
+
+    #[test]
+    fn saves_email_attachments() {
+        let dir = dir("email");
+        let email = dir.join("mail.eml");
+        fs::write(&email, EMAIL).unwrap();
+
+        let (staging, files) = Unpacker::new(10, 1024).unpack_email(&email).unwrap();
+
+        assert_eq!(files, vec![staging.join("invoice.pdf")]);
+        assert!(fs::read(&files[0]).unwrap().starts_with(b"%PDF-1.7"));
+        remove_staging(&staging);
+    }
+
+    #[test]
+    fn does_not_read_emails_past_the_size_limit() {
+        let dir = dir("large-email");
+        let email = dir.join("mail.eml");
+        fs::write(&email, EMAIL).unwrap();
+
+        let limit = EMAIL.len() as u64 - 1;
+        let result = Unpacker::new(10, limit).unpack_email(&email);
+
+        assert!(matches!(result, Err(UnpackError::TooLarge(_))));
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+    }
+}
@@ -84,6 +84,33 @@ impl FileWatcher {
         });
     }
 
+    /// Moves the watch to the `WATCH_PATH` of `config`. The old folder is
+    /// only let go once the new one is watched, so a folder that cannot be
+    /// watched leaves everything as it was.
+    pub fn retarget(&mut self, config: AppConfig) -> notify::Result<()> {
+        if config.watch_path == self.config.watch_path {
+            self.config = config;
+            return Ok(());
+        }
+
+        if let Some(watcher) = &mut self.file_watcher {
+            watcher.watch(Path::new(&config.watch_path), RecursiveMode::Recursive)?;
+
+            if let Err(err) = watcher.unwatch(Path::new(&self.config.watch_path)) {
+                log::warn!(
+                    "Failed to stop watching {}: {:?}",
+                    self.config.watch_path,
+                    err
+                );
+            }
+
+            log::info!("Watching {}", config.watch_path);
+        }
+
+        self.config = config;
+        Ok(())
+    }
+
     pub fn get_handler(&mut self) -> Arc<WatcherHandler> {
         self.handler.clone()
     }
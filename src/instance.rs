@@ -0,0 +1,121 @@
+use crate::config::AppConfig;
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum InstanceError {
+    #[error("Another instance is running{}", .0.map(|pid| format!(" with PID {}", pid)).unwrap_or_default())]
+    Running(Option<u32>),
+
+    #[error("Failed to create lock file {0}: {1}")]
+    IoError(String, io::Error),
+}
+
+/// Keeps a second instance from watching the same `WATCH_PATH`. The lock
+/// file is locked by the OS for as long as the instance runs, so the lock
+/// goes away with the process however it ends. The PID is written next to
+/// it only to tell which instance holds it.
+pub struct InstanceLock {
+    _file: File,
+    pid_path: PathBuf,
+}
+
+impl InstanceLock {
+    pub fn acquire(config: &AppConfig) -> Result<Self, InstanceError> {
+        let path = config
+            .data_dir()
+            .join(format!("autodate-{}.lock", config.profile_id()));
+
+        Self::acquire_at(&path)
+    }
+
+    fn acquire_at(path: &Path) -> Result<Self, InstanceError> {
+        let display = path.display().to_string();
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|err| InstanceError::IoError(display.clone(), err))?;
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(path)
+            .map_err(|err| InstanceError::IoError(display.clone(), err))?;
+
+        let pid_path = path.with_extension("pid");
+
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                return Err(InstanceError::Running(read_pid(&pid_path)));
+            }
+            Err(TryLockError::Error(err)) => return Err(InstanceError::IoError(display, err)),
+        }
+
+        // The PID is informational, so failing to write it doesn't give up
+        // the lock
+        if let Err(err) = fs::write(&pid_path, process::id().to_string()) {
+            log::warn!("Failed to write {}: {}", pid_path.display(), err);
+        }
+
+        Ok(Self {
+            _file: file,
+            pid_path,
+        })
+    }
+}
+
+impl Drop for InstanceLock {
+    fn drop(&mut self) {
+        // The lock file itself stays, as removing it could let another
+        // instance lock a file that is about to disappear
+        let _ = fs::remove_file(&self.pid_path);
+    }
+}
+
+fn read_pid(path: &Path) -> Option<u32> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn lock_path(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("autodate-instance-{}", name));
+        let _ = fs::remove_dir_all(&dir);
+        dir.join("autodate.lock")
+    }
+
+    #[test]
+    fn refuses_a_second_lock_while_held() {
+        let path = lock_path("held");
+        let lock = InstanceLock::acquire_at(&path).unwrap();
+
+        match InstanceLock::acquire_at(&path) {
+            Err(InstanceError::Running(pid)) => assert_eq!(pid, Some(process::id())),
+            other => panic!("unexpected {:?}", other.err()),
+        }
+
+        drop(lock);
+        assert!(InstanceLock::acquire_at(&path).is_ok());
+    }
+
+    #[test]
+    fn ignores_a_pid_left_behind() {
+        let path = lock_path("stale");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "").unwrap();
+        fs::write(path.with_extension("pid"), "1").unwrap();
+
+        let _lock = InstanceLock::acquire_at(&path).unwrap();
+
+        assert_eq!(read_pid(&path.with_extension("pid")), Some(process::id()));
+    }
+}
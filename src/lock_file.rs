@@ -0,0 +1,22 @@
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::path::Path;
+
+/// Locks the file next to `path` with the `lock` extension, waiting while
+/// another thread or process holds it. The lock is released when the file
+/// returned is dropped. Files that are replaced on save can't hold a lock
+/// themselves, so every file shared between instances is locked this way.
+pub fn lock(path: &Path) -> io::Result<File> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(path.with_extension("lock"))?;
+
+    file.lock()?;
+    Ok(file)
+}
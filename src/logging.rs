@@ -0,0 +1,294 @@
+use crate::config::app_data_dir;
+use crate::rotation::rotate;
+use env_logger::{Builder, Logger, Target};
+use log::{Log, Metadata, Record};
+use serde::Deserialize;
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{OnceLock, RwLock};
+
+const DEFAULT_LOG_FILE: &str = "autodate.log";
+
+/// The file logger, once `init` has set it up.
+static FILE_LOGGER: OnceLock<FileLogger> = OnceLock::new();
+
+/// Logging options, read on their own so logging starts before, and works
+/// without, a valid configuration.
+#[derive(Clone, Debug, Deserialize)]
+pub struct LogConfig {
+    #[serde(default = "default_log_level")]
+    pub log_level: String,
+    pub log_file: Option<String>,
+    #[serde(default = "default_log_max_size_mb")]
+    pub log_max_size_mb: u64,
+    #[serde(default = "default_log_keep_files")]
+    pub log_keep_files: usize,
+    /// Why the options were invalid, logged once logging is set up.
+    #[serde(skip)]
+    pub warning: Option<String>,
+}
+
+impl LogConfig {
+    /// Reads the options from the environment, falling back to the defaults
+    /// when they are invalid.
+    pub fn load() -> Self {
+        Self::load_from(env::vars())
+    }
+
+    /// Reads the options from `vars`, as `load` does from the environment.
+    pub fn load_from(vars: impl IntoIterator<Item = (String, String)>) -> Self {
+        envy::from_iter(vars).unwrap_or_else(|err| Self {
+            warning: Some(format!(
+                "Invalid logging configuration, using defaults: {}",
+                err
+            )),
+            ..envy::from_iter(Vec::<(String, String)>::new()).expect("Defaults for every option")
+        })
+    }
+
+    /// Maximum size of the log file in bytes. `LOG_MAX_SIZE_MB=0` never
+    /// rotates it.
+    fn max_size(&self) -> u64 {
+        match self.log_max_size_mb {
+            0 => u64::MAX,
+            mb => mb.saturating_mul(1024 * 1024),
+        }
+    }
+
+    /// `LOG_FILE`, or `autodate.log` in the per-user data directory.
+    pub fn path(&self) -> PathBuf {
+        match &self.log_file {
+            Some(path) => PathBuf::from(path),
+            None => app_data_dir()
+                .unwrap_or_else(env::temp_dir)
+                .join(DEFAULT_LOG_FILE),
+        }
+    }
+}
+
+/// Logs to the rotating log file, and to stderr for when there is a
+/// console.
+pub fn init(config: &LogConfig) {
+    let logger = FILE_LOGGER.get_or_init(|| FileLogger {
+        logger: RwLock::new(file_logger(config)),
+        path: RwLock::new(config.path()),
+    });
+
+    log::set_max_level(logger.logger.read().unwrap().filter());
+
+    if log::set_logger(logger).is_err() {
+        eprintln!("A logger is already set up");
+    }
+
+    report(config);
+}
+
+/// Switches the file logger to new options, as `init` would have set it up
+/// with them.
+pub fn reload(config: &LogConfig) {
+    let Some(logger) = FILE_LOGGER.get() else {
+        return;
+    };
+
+    let new = file_logger(config);
+    log::set_max_level(new.filter());
+
+    *logger.logger.write().unwrap() = new;
+    *logger.path.write().unwrap() = config.path();
+
+    report(config);
+}
+
+/// The file being logged to.
+pub fn log_path() -> PathBuf {
+    match FILE_LOGGER.get() {
+        Some(logger) => logger.path.read().unwrap().clone(),
+        None => LogConfig::load().path(),
+    }
+}
+
+/// Logs to stderr only, for commands run from a terminal.
+pub fn init_console(config: &LogConfig) {
+    builder(config).init();
+    report(config);
+}
+
+/// Logs the problem with the options, which could not be logged while
+/// reading them.
+fn report(config: &LogConfig) {
+    if let Some(warning) = &config.warning {
+        log::warn!("{}", warning);
+    }
+}
+
+fn builder(config: &LogConfig) -> Builder {
+    let mut builder = Builder::new();
+    builder.parse_filters(&config.log_level);
+    builder
+}
+
+fn file_logger(config: &LogConfig) -> Logger {
+    let file = RotatingFile::new(&config.path(), config.max_size(), config.log_keep_files);
+
+    builder(config).target(Target::Pipe(Box::new(file))).build()
+}
+
+/// Passes records on to a logger that a reload can replace.
+struct FileLogger {
+    logger: RwLock<Logger>,
+    path: RwLock<PathBuf>,
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.logger.read().unwrap().enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        self.logger.read().unwrap().log(record);
+    }
+
+    fn flush(&self) {
+        self.logger.read().unwrap().flush();
+    }
+}
+
+/// Appends to a file, rotating it once it would grow past `max_size` bytes
+/// and keeping `keep` rotated files. Everything is also copied to stderr.
+struct RotatingFile {
+    path: PathBuf,
+    max_size: u64,
+    keep: usize,
+    file: Option<File>,
+    size: u64,
+}
+
+impl RotatingFile {
+    fn new(path: &Path, max_size: u64, keep: usize) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            max_size,
+            keep,
+            file: None,
+            size: 0,
+        }
+    }
+
+    fn write_file(&mut self, buf: &[u8]) -> io::Result<()> {
+        if self.size > 0 && self.size.saturating_add(buf.len() as u64) > self.max_size {
+            self.file = None;
+            rotate(&self.path, self.keep)?;
+        }
+
+        let file = match &mut self.file {
+            Some(file) => file,
+            None => {
+                if let Some(parent) = self.path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+
+                let file = OpenOptions::new()
+                    .append(true)
+                    .create(true)
+                    .open(&self.path)?;
+
+                self.size = file.metadata()?.len();
+                self.file.insert(file)
+            }
+        };
+
+        file.write_all(buf)?;
+        self.size += buf.len() as u64;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // A log that cannot be written must not stop the app, and stderr
+        // is often not there at all
+        if let Err(err) = self.write_file(buf) {
+            self.file = None;
+            let _ = writeln!(io::stderr(), "Failed to write log file: {}", err);
+        }
+
+        let _ = io::stderr().write_all(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if let Some(file) = &mut self.file {
+            file.flush()?;
+        }
+        Ok(())
+    }
+}
+
+fn default_log_level() -> String {
+    "info".to_string()
+}
+
+fn default_log_max_size_mb() -> u64 {
+    5
+}
+
+fn default_log_keep_files() -> usize {
+    5
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rotation::rotated_path;
+
+    fn load(vars: &[(&str, &str)]) -> LogConfig {
+        LogConfig::load_from(
+            vars.iter()
+                .map(|(key, value)| (key.to_string(), value.to_string())),
+        )
+    }
+
+    #[test]
+    fn keeps_the_reason_for_falling_back() {
+        let config = load(&[("LOG_MAX_SIZE_MB", "lots")]);
+
+        assert_eq!(config.log_max_size_mb, default_log_max_size_mb());
+        assert!(config.warning.unwrap().contains("lots"));
+        assert!(load(&[("LOG_MAX_SIZE_MB", "1")]).warning.is_none());
+    }
+
+    #[test]
+    fn limits_the_size_without_overflowing() {
+        assert_eq!(
+            load(&[("LOG_MAX_SIZE_MB", "2")]).max_size(),
+            2 * 1024 * 1024
+        );
+        assert_eq!(load(&[("LOG_MAX_SIZE_MB", "0")]).max_size(), u64::MAX);
+
+        let huge = u64::MAX.to_string();
+        assert_eq!(load(&[("LOG_MAX_SIZE_MB", &huge)]).max_size(), u64::MAX);
+    }
+
+    #[test]
+    fn rotates_before_growing_past_the_size() {
+        let dir = env::temp_dir().join("autodate-logging");
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join("autodate.log");
+
+        let mut file = RotatingFile::new(&path, 10, 1);
+        file.write_file(b"12345678\n").unwrap();
+        file.write_file(b"abc\n").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "abc\n");
+        assert_eq!(
+            fs::read_to_string(rotated_path(&path, 1)).unwrap(),
+            "12345678\n"
+        );
+
+        let mut file = RotatingFile::new(&path, u64::MAX, 1);
+        file.write_file(b"more\n").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "abc\nmore\n");
+    }
+}
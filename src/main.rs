@@ -1,21 +1,72 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 mod app;
+mod audit;
+mod cli;
 mod config;
+mod control;
+mod date;
 mod file;
+mod instance;
+mod lock_file;
+mod logging;
+mod monitor;
+mod reload;
+mod rotation;
+mod service;
+mod source;
+mod state;
+mod toml_file;
 mod tray;
 
 use crate::app::App;
+use crate::cli::{Cli, Command};
 use crate::config::AppConfig;
+use crate::instance::{InstanceError, InstanceLock};
+use crate::logging::LogConfig;
+use crate::reload::ConfigSource;
+use clap::Parser;
+use std::process::ExitCode;
 
-fn main() {
-    dotenvy::dotenv().ok();
+fn main() -> ExitCode {
+    let source = ConfigSource::load();
 
-    env_logger::Builder::from_env(env_logger::Env::default()).init();
+    if std::env::args_os().len() > 1 {
+        cli::attach_console();
+    }
+
+    let cli = Cli::parse();
+    let log_config = LogConfig::load();
+
+    match &cli.command {
+        None | Some(Command::Headless) => logging::init(&log_config),
+        Some(_) => logging::init_console(&log_config),
+    }
+
+    if let Some(command) = cli.command {
+        return command.run(source);
+    }
 
     let config = AppConfig::new();
 
+    let _lock = match InstanceLock::acquire(&config) {
+        Ok(lock) => Some(lock),
+        Err(InstanceError::Running(pid)) => return cli::forward(&config, pid, &cli.files),
+        Err(err) => {
+            log::warn!("{}, starting without a lock", err);
+            None
+        }
+    };
+
     log::info!("Initializing application");
 
-    App::new(config).listen_files().run_tray();
+    App::new(config, source)
+        .rename_files(cli.files)
+        .listen_files()
+        .check_missing()
+        .serve_control()
+        .watch_config()
+        .run_tray();
+
+    ExitCode::SUCCESS
 }
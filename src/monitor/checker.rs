@@ -0,0 +1,214 @@
+use crate::config::AppConfig;
+use crate::date::matcher::NameMatcher;
+use crate::file::extension::split_file_name;
+use crate::file::sanitizer::Sanitizer;
+use crate::file::unpacker::is_staging;
+use crate::monitor::expectations::Expectation;
+use chrono::{Days, Months, NaiveDate};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A period for which an expected document has not arrived.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Missing {
+    pub name: String,
+    pub period: String,
+    pub due: NaiveDate,
+}
+
+/// A renamed file and the date in its name.
+struct Filed {
+    path: PathBuf,
+    date: NaiveDate,
+}
+
+/// Looks through the renamed files in `WATCH_PATH` for the periods of each
+/// expectation that are past due on `today` and have no document.
+pub fn find_missing(config: &AppConfig, today: NaiveDate) -> Result<Vec<Missing>, regex::Error> {
+    if config.expectations.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let matchers = NameMatcher::for_config(config)?;
+    let root = Path::new(&config.watch_path);
+
+    let mut files = Vec::new();
+    collect_files(root, &config.quarantine_dir(), &mut files);
+
+    let filed: Vec<Filed> = files
+        .into_iter()
+        .filter_map(|path| {
+            let name = path.file_name()?.to_str()?;
+            let (stem, _) = split_file_name(name, &config.compound_extensions);
+            let date = matchers.iter().find_map(|matcher| matcher.parse(stem))?;
+            Some(Filed { path, date })
+        })
+        .collect();
+
+    let sanitizer = Sanitizer::new(&config.name_replacement, config.max_name_length);
+    let mut missing = Vec::new();
+
+    for expectation in config.expectations.iter() {
+        missing.extend(check(expectation, root, &filed, &sanitizer, today));
+    }
+
+    Ok(missing)
+}
+
+fn check(
+    expectation: &Expectation,
+    root: &Path,
+    filed: &[Filed],
+    sanitizer: &Sanitizer,
+    today: NaiveDate,
+) -> Vec<Missing> {
+    let cadence = expectation.cadence;
+
+    let found: HashSet<NaiveDate> = filed
+        .iter()
+        .filter(|file| matches(expectation, root, &file.path, sanitizer))
+        .map(|file| cadence.period_start(file.date))
+        .collect();
+
+    let since = expectation
+        .since
+        .or_else(|| today.checked_sub_months(Months::new(12)))
+        .unwrap_or(today);
+
+    let mut missing = Vec::new();
+    let mut start = cadence.period_start(since);
+
+    while start <= today {
+        let due = due_date(start, expectation.due_day);
+
+        if due < today && !found.contains(&start) {
+            missing.push(Missing {
+                name: expectation.name.clone(),
+                period: cadence.label(start),
+                due,
+            });
+        }
+
+        let Some(next) = start.checked_add_months(Months::new(cadence.months())) else {
+            break;
+        };
+        start = next;
+    }
+
+    missing
+}
+
+/// The due day of the first month of the period, or its last day when the
+/// month is shorter.
+fn due_date(start: NaiveDate, due_day: u32) -> NaiveDate {
+    let last = start
+        .checked_add_months(Months::new(1))
+        .and_then(|next| next.checked_sub_days(Days::new(1)))
+        .unwrap_or(start);
+
+    start
+        .checked_add_days(Days::new(u64::from(due_day.saturating_sub(1))))
+        .filter(|due| *due <= last)
+        .unwrap_or(last)
+}
+
+fn matches(expectation: &Expectation, root: &Path, path: &Path, sanitizer: &Sanitizer) -> bool {
+    if let Some(folder) = &expectation.folder
+        && !path.starts_with(root.join(folder))
+    {
+        return false;
+    }
+
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+
+    if expectation
+        .pattern
+        .as_ref()
+        .is_some_and(|pattern| !pattern.is_match(&name))
+    {
+        return false;
+    }
+
+    match &expectation.vendor {
+        Some(vendor) => {
+            // VENDOR_FOLDERS names the folder after the sanitized vendor
+            let folder = sanitizer.sanitize(vendor, "").to_lowercase();
+            let vendor = vendor.to_lowercase();
+            let in_folder = path
+                .strip_prefix(root)
+                .ok()
+                .and_then(Path::parent)
+                .is_some_and(|parent| {
+                    parent.components().any(|component| {
+                        let component = component.as_os_str().to_string_lossy().to_lowercase();
+                        component == vendor || component == folder
+                    })
+                });
+
+            in_folder || name.to_lowercase().contains(&vendor)
+        }
+        None => true,
+    }
+}
+
+/// Adds every file under `dir` to `files`, leaving out the quarantine and
+/// staging folders. Links to folders are not followed, as they can loop.
+pub fn collect_files(dir: &Path, quarantine: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+
+        if path.starts_with(quarantine) || is_staging(&path) {
+            continue;
+        }
+
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+
+        if file_type.is_dir() {
+            collect_files(&path, quarantine, files);
+        } else if file_type.is_symlink() && path.is_dir() {
+            log::debug!("Not following linked folder {}", path.display());
+        } else {
+            files.push(path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[cfg(unix)]
+    #[test]
+    fn does_not_follow_linked_folders() {
+        let root = env::temp_dir().join("autodate-checker-links");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("2025")).unwrap();
+        fs::write(root.join("2025").join("2025-10.pdf"), b"x").unwrap();
+        std::os::unix::fs::symlink(&root, root.join("2025").join("loop")).unwrap();
+
+        let mut files = Vec::new();
+        collect_files(&root, &root.join("quarantine"), &mut files);
+
+        assert_eq!(files, vec![root.join("2025").join("2025-10.pdf")]);
+    }
+
+    #[test]
+    fn ends_periods_on_their_due_day() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+
+        assert_eq!(due_date(date(2025, 10, 1), 10), date(2025, 10, 10));
+        assert_eq!(due_date(date(2025, 2, 1), 31), date(2025, 2, 28));
+        assert_eq!(due_date(date(2025, 10, 1), 0), date(2025, 10, 1));
+    }
+}
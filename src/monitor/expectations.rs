@@ -0,0 +1,92 @@
+use crate::toml_file::{self, TomlFileError, deserialize_regex};
+use chrono::{Datelike, NaiveDate};
+use regex::Regex;
+use serde::{Deserialize, Deserializer};
+use std::path::Path;
+
+/// Recurring documents that should show up in `WATCH_PATH`, read from
+/// `EXPECTATIONS_FILE`.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct Expectations {
+    #[serde(default, rename = "expect")]
+    expectations: Vec<Expectation>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Cadence {
+    Monthly,
+    Quarterly,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Expectation {
+    pub name: String,
+    /// Files whose name contains it, or that are in a folder named after
+    /// it, ignoring case.
+    pub vendor: Option<String>,
+    /// Regex searched in the file name.
+    #[serde(default, deserialize_with = "deserialize_regex")]
+    pub pattern: Option<Regex>,
+    /// Folder to look in, relative to `WATCH_PATH`.
+    pub folder: Option<String>,
+    pub cadence: Cadence,
+    /// Day of the first month of the period by which the document is due.
+    #[serde(default = "default_due_day")]
+    pub due_day: u32,
+    /// First period to check, as `YYYY-MM`. Defaults to a year ago.
+    #[serde(default, deserialize_with = "deserialize_since")]
+    pub since: Option<NaiveDate>,
+}
+
+impl Expectations {
+    pub fn load(path: &Path) -> Result<Self, TomlFileError> {
+        toml_file::load(path, "expectations")
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.expectations.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Expectation> {
+        self.expectations.iter()
+    }
+}
+
+impl Cadence {
+    pub fn months(&self) -> u32 {
+        match self {
+            Cadence::Monthly => 1,
+            Cadence::Quarterly => 3,
+        }
+    }
+
+    /// First day of the period `date` is in.
+    pub fn period_start(&self, date: NaiveDate) -> NaiveDate {
+        let month0 = date.month0() - date.month0() % self.months();
+        NaiveDate::from_ymd_opt(date.year(), month0 + 1, 1).expect("Valid period start")
+    }
+
+    pub fn label(&self, start: NaiveDate) -> String {
+        match self {
+            Cadence::Monthly => start.format("%Y-%m").to_string(),
+            Cadence::Quarterly => format!("{}-Q{}", start.year(), start.month0() / 3 + 1),
+        }
+    }
+}
+
+fn default_due_day() -> u32 {
+    1
+}
+
+fn deserialize_since<'de, D>(deserializer: D) -> Result<Option<NaiveDate>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let since = String::deserialize(deserializer)?;
+
+    NaiveDate::parse_from_str(&format!("{}-01", since), "%Y-%m-%d")
+        .map(Some)
+        .map_err(|_| serde::de::Error::custom(format!("`{}` is not a YYYY-MM month", since)))
+}
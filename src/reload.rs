@@ -0,0 +1,243 @@
+use crate::config::{AppConfig, AppConfigError, SharedConfig};
+use crate::file::watcher::FileWatcher;
+use crate::logging::{self, LogConfig};
+use notify::{Config, Event, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::HashMap;
+use std::env;
+use std::path::{self, PathBuf};
+use std::sync::mpsc::channel;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// How long the configuration files must stay unchanged before a reload,
+/// since editors save in several steps.
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+pub type ErrorCallback = Arc<dyn Fn(AppConfigError) + Send + Sync>;
+
+/// Where the configuration comes from: the environment the app was started
+/// with, and the `.env` file found then, or the one that can be created in
+/// the working directory. Keeping them apart lets `.env` be read again
+/// without changing the environment of the running app.
+#[derive(Clone, Debug)]
+pub struct ConfigSource {
+    environment: HashMap<String, String>,
+    env_file: Option<PathBuf>,
+}
+
+impl ConfigSource {
+    /// Takes the environment, then loads `.env` into it.
+    pub fn load() -> Self {
+        let environment = env::vars_os()
+            .filter_map(|(key, value)| Some((key.into_string().ok()?, value.into_string().ok()?)))
+            .collect();
+
+        Self {
+            environment,
+            env_file: dotenvy::dotenv()
+                .ok()
+                .or_else(|| env::current_dir().ok().map(|dir| dir.join(".env"))),
+        }
+    }
+
+    /// Reads `.env` again. Variables set in the environment still take
+    /// precedence over it, as they do on start.
+    pub fn read(&self) -> Result<AppConfig, AppConfigError> {
+        AppConfig::load_from(self.vars()?)
+    }
+
+    /// The variables `.env` and the environment set together.
+    fn vars(&self) -> Result<HashMap<String, String>, AppConfigError> {
+        let mut vars = match &self.env_file {
+            Some(path) if path.exists() => dotenvy::from_path_iter(path)
+                .and_then(|iter| iter.collect::<Result<HashMap<_, _>, _>>())
+                .map_err(|err| AppConfigError::LoadError(format!("{}: {}", path.display(), err)))?,
+            _ => HashMap::new(),
+        };
+
+        vars.extend(self.environment.clone());
+        Ok(vars)
+    }
+}
+
+/// Replaces the running configuration with a new one read from its source,
+/// keeping the current one when the new one is invalid.
+#[derive(Clone)]
+pub struct Reloader {
+    config: SharedConfig,
+    file_watcher: Arc<Mutex<FileWatcher>>,
+    source: Arc<ConfigSource>,
+}
+
+impl Reloader {
+    pub fn new(
+        config: SharedConfig,
+        file_watcher: Arc<Mutex<FileWatcher>>,
+        source: Arc<ConfigSource>,
+    ) -> Self {
+        Self {
+            config,
+            file_watcher,
+            source,
+        }
+    }
+
+    pub fn reload(&self) -> Result<(), AppConfigError> {
+        // Holding the watcher keeps reloads from overlapping
+        let mut file_watcher = self.file_watcher.lock().unwrap();
+
+        let result = self.source.vars().and_then(|vars| {
+            let config = AppConfig::load_from(vars.clone())?;
+
+            file_watcher.retarget(config.clone()).map_err(|err| {
+                AppConfigError::LoadError(format!("Failed to watch {}: {}", config.watch_path, err))
+            })?;
+
+            Ok((config, LogConfig::load_from(vars)))
+        });
+
+        match result {
+            Ok((config, log_config)) => {
+                *self.config.write().unwrap() = config;
+                logging::reload(&log_config);
+                log::info!("Configuration reloaded");
+                Ok(())
+            }
+            Err(err) => {
+                log::error!("Keeping the current configuration: {}", err);
+                Err(err)
+            }
+        }
+    }
+
+    /// `.env` and the files it points to, as absolute paths.
+    pub fn files(&self) -> Vec<PathBuf> {
+        let config_files = self.config.read().unwrap().files();
+
+        self.source
+            .env_file
+            .iter()
+            .cloned()
+            .chain(config_files)
+            .map(|path| path::absolute(&path).unwrap_or(path))
+            .collect()
+    }
+
+    /// Reloads when any of `files` changes, watching the files of the new
+    /// configuration from then on.
+    pub fn watch_files(self, on_error: ErrorCallback) -> notify::Result<()> {
+        let (sender, receiver) = channel();
+        let mut watcher = RecommendedWatcher::new(sender, Config::default())?;
+
+        let mut files = self.files();
+        let mut folders = watch_folders(&mut watcher, &files);
+
+        thread::spawn(move || {
+            while let Ok(event) = receiver.recv() {
+                if !changes(&event, &files) {
+                    continue;
+                }
+
+                while receiver.recv_timeout(DEBOUNCE).is_ok() {}
+
+                log::info!("Configuration files changed");
+
+                if let Err(err) = self.reload() {
+                    on_error(err);
+                }
+
+                for folder in &folders {
+                    let _ = watcher.unwatch(folder);
+                }
+
+                files = self.files();
+                folders = watch_folders(&mut watcher, &files);
+            }
+        });
+
+        Ok(())
+    }
+
+    /// Reloads on `SIGHUP`, as daemons do.
+    #[cfg(unix)]
+    pub fn watch_signals(self, on_error: ErrorCallback) -> std::io::Result<()> {
+        use signal_hook::consts::SIGHUP;
+        use signal_hook::iterator::Signals;
+
+        let mut signals = Signals::new([SIGHUP])?;
+
+        thread::spawn(move || {
+            for _ in signals.forever() {
+                log::info!("Received SIGHUP");
+
+                if let Err(err) = self.reload() {
+                    on_error(err);
+                }
+            }
+        });
+
+        Ok(())
+    }
+}
+
+/// Watches the folders holding `files` rather than the files themselves,
+/// which editors often replace when saving.
+fn watch_folders(watcher: &mut RecommendedWatcher, files: &[PathBuf]) -> Vec<PathBuf> {
+    let mut folders: Vec<PathBuf> = Vec::new();
+
+    for folder in files.iter().filter_map(|file| file.parent()) {
+        if folders.iter().any(|watched| watched == folder) {
+            continue;
+        }
+
+        match watcher.watch(folder, RecursiveMode::NonRecursive) {
+            Ok(()) => folders.push(folder.to_path_buf()),
+            Err(err) => log::warn!("Failed to watch {}: {:?}", folder.display(), err),
+        }
+    }
+
+    folders
+}
+
+fn changes(event: &notify::Result<Event>, files: &[PathBuf]) -> bool {
+    match event {
+        Ok(event) => !event.kind.is_access() && event.paths.iter().any(|path| files.contains(path)),
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn reads_env_file_created_later() {
+        let dir = env::temp_dir().join("autodate-reload-tests");
+        fs::create_dir_all(&dir).unwrap();
+        let env_file = dir.join(".env");
+        let _ = fs::remove_file(&env_file);
+
+        let source = ConfigSource {
+            environment: HashMap::from([("DELAY_SECONDS".to_string(), "7".to_string())]),
+            env_file: Some(env_file.clone()),
+        };
+
+        assert!(source.read().is_err());
+
+        fs::write(
+            &env_file,
+            "WATCH_PATH=/watch\nFILE_FORMAT=%Y-%m\nDELAY_SECONDS=1\nLOG_LEVEL=debug\n",
+        )
+        .unwrap();
+
+        // The environment still takes precedence over `.env`
+        let config = source.read().unwrap();
+        assert_eq!(config.file_format, "%Y-%m");
+        assert_eq!(config.delay_seconds, 7);
+
+        let log_config = LogConfig::load_from(source.vars().unwrap());
+        assert_eq!(log_config.log_level, "debug");
+    }
+}
@@ -0,0 +1,120 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Moves `path` to `<name>.1.<ext>`, shifting the older copies up by one
+/// and deleting the one past `keep`. With `keep` at 0 the file is deleted.
+pub fn rotate(path: &Path, keep: usize) -> io::Result<()> {
+    if keep == 0 {
+        return fs::remove_file(path);
+    }
+
+    let oldest = rotated_path(path, keep);
+    if oldest.exists() {
+        fs::remove_file(oldest)?;
+    }
+
+    for n in (1..keep).rev() {
+        let from = rotated_path(path, n);
+        if from.exists() {
+            fs::rename(from, rotated_path(path, n + 1))?;
+        }
+    }
+
+    fs::rename(path, rotated_path(path, 1))
+}
+
+/// Whether `path` is `original` or one of its rotated copies.
+pub fn is_rotation_of(path: &Path, original: &Path) -> bool {
+    if path == original {
+        return true;
+    }
+
+    if path.parent() != original.parent() {
+        return false;
+    }
+
+    let (Some(name), Some(stem)) = (path.file_name(), original.file_stem()) else {
+        return false;
+    };
+
+    let name = name.to_string_lossy();
+    let Some(rest) = name.strip_prefix(&format!("{}.", stem.to_string_lossy())) else {
+        return false;
+    };
+
+    let number = match original.extension() {
+        Some(extension) => rest.strip_suffix(&format!(".{}", extension.to_string_lossy())),
+        None => Some(rest),
+    };
+
+    number.is_some_and(|number| !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Path of the `n`th rotated copy of `path`.
+pub fn rotated_path(path: &Path, n: usize) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let name = match path.extension() {
+        Some(extension) => format!("{}.{}.{}", stem, n, extension.to_string_lossy()),
+        None => format!("{}.{}", stem, n),
+    };
+
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn shifts_copies_and_drops_the_oldest() {
+        let dir = env::temp_dir().join("autodate-rotation");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("audit.jsonl");
+
+        for content in ["first", "second", "third"] {
+            fs::write(&path, content).unwrap();
+            rotate(&path, 2).unwrap();
+        }
+
+        assert!(!path.exists());
+        assert_eq!(
+            fs::read_to_string(dir.join("audit.1.jsonl")).unwrap(),
+            "third"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join("audit.2.jsonl")).unwrap(),
+            "second"
+        );
+        assert!(!dir.join("audit.3.jsonl").exists());
+
+        fs::write(&path, "fourth").unwrap();
+        rotate(&path, 0).unwrap();
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn recognizes_rotated_copies() {
+        let original = Path::new("/data/audit.jsonl");
+
+        assert!(is_rotation_of(original, original));
+        assert!(is_rotation_of(Path::new("/data/audit.3.jsonl"), original));
+        assert!(!is_rotation_of(Path::new("/data/audit.x.jsonl"), original));
+        assert!(!is_rotation_of(Path::new("/data/audit..jsonl"), original));
+        assert!(!is_rotation_of(Path::new("/other/audit.1.jsonl"), original));
+        assert!(!is_rotation_of(Path::new("/data/audit.1.log"), original));
+
+        let log = Path::new("/data/autodate.log");
+        assert!(is_rotation_of(Path::new("/data/autodate.2.log"), log));
+
+        let bare = Path::new("/data/state");
+        assert!(is_rotation_of(Path::new("/data/state.1"), bare));
+        assert!(!is_rotation_of(Path::new("/data/state.lock"), bare));
+    }
+}
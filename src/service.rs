@@ -0,0 +1,165 @@
+use crate::config::{AppConfig, AppConfigError, SharedConfig};
+use crate::control::server::Controller;
+use crate::file::handler::WatcherHandler;
+use crate::file::renamer::FileRenamer;
+use crate::file::watcher::FileWatcher;
+use crate::monitor::checker::{Missing, find_missing};
+use crate::reload::{ConfigSource, Reloader};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread;
+use std::time::Duration;
+
+/// How often expected documents are checked for.
+const MISSING_CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// The work done in the background, with or without a tray icon: renaming
+/// new files, checking for missing documents, answering control requests
+/// and reloading the configuration.
+pub struct Service {
+    config: SharedConfig,
+    file_watcher: Arc<Mutex<FileWatcher>>,
+    source: Arc<ConfigSource>,
+}
+
+impl Service {
+    /// `source` is where `config` was read from, read again on reload.
+    pub fn new(config: AppConfig, source: ConfigSource) -> Self {
+        Self {
+            file_watcher: Arc::new(Mutex::new(FileWatcher::new(config.clone()))),
+            config: Arc::new(RwLock::new(config)),
+            source: Arc::new(source),
+        }
+    }
+
+    pub fn config(&self) -> SharedConfig {
+        self.config.clone()
+    }
+
+    pub fn handler(&mut self) -> Arc<WatcherHandler> {
+        self.file_watcher.lock().unwrap().get_handler()
+    }
+
+    pub fn reloader(&self) -> Reloader {
+        Reloader::new(
+            self.config.clone(),
+            self.file_watcher.clone(),
+            self.source.clone(),
+        )
+    }
+
+    pub fn listen_files(&mut self) -> &mut Self {
+        let config = self.config.clone();
+
+        self.file_watcher.lock().unwrap().listen(move |path| {
+            let config = config.read().unwrap().clone();
+
+            if let Err(err) = FileRenamer::new(config).rename_file(path) {
+                log::error!("Failed to rename file {}: {}", path.display(), err);
+            }
+        });
+
+        self
+    }
+
+    /// Renames files given on the command line, in the background.
+    pub fn rename_files(&mut self, files: Vec<PathBuf>) -> &mut Self {
+        if files.is_empty() {
+            return self;
+        }
+
+        let config = self.config.read().unwrap().clone();
+
+        thread::spawn(move || {
+            let renamer = FileRenamer::new(config);
+
+            for file in files {
+                if let Err(err) = renamer.rename_now(&file) {
+                    log::error!("Failed to rename file {}: {}", file.display(), err);
+                }
+            }
+        });
+
+        self
+    }
+
+    /// Checks for expected documents that have not arrived, on start and
+    /// then every hour, logging new gaps and passing every check's result
+    /// to `notify`.
+    pub fn check_missing(&mut self, notify: impl Fn(&[Missing]) + Send + 'static) -> &mut Self {
+        let config = self.config.clone();
+
+        thread::spawn(move || {
+            let mut reported = Vec::new();
+
+            loop {
+                let config = config.read().unwrap().clone();
+
+                let result = if config.expectations.is_empty() {
+                    Ok(Vec::new())
+                } else {
+                    find_missing(&config, config.timezone.now().date_naive())
+                };
+
+                match result {
+                    Ok(missing) => {
+                        for entry in missing.iter().filter(|entry| !reported.contains(*entry)) {
+                            log::warn!(
+                                "Missing {} for {} (due {})",
+                                entry.name,
+                                entry.period,
+                                entry.due
+                            );
+                        }
+
+                        notify(&missing);
+                        reported = missing;
+                    }
+                    Err(err) => log::error!("Failed to check for missing documents: {}", err),
+                }
+
+                thread::sleep(MISSING_CHECK_INTERVAL);
+            }
+        });
+
+        self
+    }
+
+    /// Starts the control endpoint used by `autodate ctl`. `on_pause` is
+    /// told when a request pauses or resumes the watcher.
+    pub fn serve_control(&mut self, on_pause: impl Fn(bool) + Send + 'static) -> &mut Self {
+        let controller = Controller::new(
+            self.config.clone(),
+            self.reloader(),
+            self.handler(),
+            on_pause,
+        );
+
+        if let Err(err) = controller.serve() {
+            log::error!("Failed to start control endpoint: {}", err);
+        }
+
+        self
+    }
+
+    /// Reloads the configuration when `.env` or the files it points to
+    /// change, and on `SIGHUP`. `on_error` is given the errors of a new
+    /// configuration that was not taken.
+    pub fn watch_config(
+        &mut self,
+        on_error: impl Fn(AppConfigError) + Send + Sync + 'static,
+    ) -> &mut Self {
+        let on_error = Arc::new(on_error);
+
+        if let Err(err) = self.reloader().watch_files(on_error.clone()) {
+            log::error!("Failed to watch configuration files: {}", err);
+        }
+
+        #[cfg(unix)]
+        if let Err(err) = self.reloader().watch_signals(on_error) {
+            log::error!("Failed to listen for SIGHUP: {}", err);
+        }
+
+        self
+    }
+}
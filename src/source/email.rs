@@ -0,0 +1,139 @@
+use crate::date::timezone::Timezone;
+use crate::file::template::Fields;
+use chrono::{DateTime, FixedOffset};
+use mailparse::{
+    DispositionType, MailAddr, MailHeaderMap, MailParseError, ParsedMail, addrparse_header,
+    dateparse, parse_headers,
+};
+use std::fs;
+use std::path::Path;
+
+/// Headers of a saved email (`.eml`).
+#[derive(Debug, Default, PartialEq)]
+pub struct Email {
+    pub date: Option<DateTime<FixedOffset>>,
+    pub from: Option<String>,
+    pub sender_domain: Option<String>,
+    pub subject: Option<String>,
+}
+
+impl Email {
+    /// Reads the email at `path`, unless it is larger than `max_size` bytes.
+    pub fn load(path: &Path, timezone: &Timezone, max_size: u64) -> Option<Self> {
+        if fs::metadata(path).ok()?.len() > max_size {
+            log::warn!(
+                "Email {} is larger than {} bytes, ignoring its headers",
+                path.display(),
+                max_size
+            );
+            return None;
+        }
+
+        let data = fs::read(path).ok()?;
+
+        match Self::parse(&data, timezone) {
+            Ok(email) => Some(email),
+            Err(err) => {
+                log::warn!("Failed to parse email {}: {}", path.display(), err);
+                None
+            }
+        }
+    }
+
+    /// Reads the `Date`, `From` and `Subject` headers.
+    pub fn parse(data: &[u8], timezone: &Timezone) -> Result<Self, MailParseError> {
+        let (headers, _) = parse_headers(data)?;
+
+        let date = headers
+            .get_first_value("Date")
+            .and_then(|date| dateparse(&date).ok())
+            .and_then(|timestamp| DateTime::from_timestamp(timestamp, 0))
+            .map(|date| timezone.convert(date));
+
+        let from = headers
+            .get_first_header("From")
+            .and_then(|header| addrparse_header(header).ok())
+            .and_then(|addresses| {
+                addresses.iter().find_map(|address| match address {
+                    MailAddr::Single(info) => Some(info.addr.clone()),
+                    MailAddr::Group(group) => group.addrs.first().map(|info| info.addr.clone()),
+                })
+            });
+
+        let sender_domain = from
+            .as_deref()
+            .and_then(|from| from.rsplit_once('@'))
+            .map(|(_, domain)| domain.to_lowercase());
+
+        let subject = headers
+            .get_first_value("Subject")
+            .map(|subject| subject.trim().to_string())
+            .filter(|subject| !subject.is_empty());
+
+        Ok(Self {
+            date,
+            from,
+            sender_domain,
+            subject,
+        })
+    }
+
+    pub fn fields(&self) -> Fields {
+        [
+            ("email_from", &self.from),
+            ("sender_domain", &self.sender_domain),
+            ("email_subject", &self.subject),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| Some((name.to_string(), value.clone()?)))
+        .collect()
+    }
+}
+
+/// Returns the attached files of `mail` with their names. Inline parts
+/// only count when they carry a file name, which is how some clients send
+/// attachments.
+pub fn attachments<'a>(mail: &'a ParsedMail<'a>) -> Vec<(String, &'a ParsedMail<'a>)> {
+    mail.parts()
+        .filter(|part| part.subparts.is_empty())
+        .filter_map(|part| {
+            let disposition = part.get_content_disposition();
+
+            let name = disposition
+                .params
+                .get("filename")
+                .or_else(|| part.ctype.params.get("name"))
+                .and_then(|name| Path::new(name).file_name())
+                .map(|name| name.to_string_lossy().to_string());
+
+            match (disposition.disposition, name) {
+                (DispositionType::Attachment, Some(name)) => Some((name, part)),
+                (DispositionType::Attachment, None) => Some(("attachment".to_string(), part)),
+                (DispositionType::Inline, Some(name)) => Some((name, part)),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn ignores_emails_past_the_size_limit() {
+        let dir = env::temp_dir().join("autodate-email");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("mail.eml");
+        let content = "From: Billing <billing@Acme.com>\r\nSubject: Invoice\r\n\r\nBody";
+        fs::write(&path, content).unwrap();
+
+        let timezone = Timezone::default();
+        let email = Email::load(&path, &timezone, 1024).unwrap();
+        assert_eq!(email.sender_domain.as_deref(), Some("acme.com"));
+        assert_eq!(email.subject.as_deref(), Some("Invoice"));
+
+        assert!(Email::load(&path, &timezone, content.len() as u64 - 1).is_none());
+    }
+}
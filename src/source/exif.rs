@@ -0,0 +1,163 @@
+use crate::date::timezone::Timezone;
+use ::exif::{Exif, In, Reader, Tag, Value};
+use chrono::{DateTime, FixedOffset, NaiveDate};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+/// Date tags in order of preference, each with the tag holding its offset.
+const DATE_TAGS: [(Tag, Tag); 3] = [
+    (Tag::DateTimeOriginal, Tag::OffsetTimeOriginal),
+    (Tag::DateTimeDigitized, Tag::OffsetTimeDigitized),
+    (Tag::DateTime, Tag::OffsetTime),
+];
+
+/// Reads the moment a photo was taken from its EXIF data. Dates without an
+/// offset are taken to be in the configured timezone.
+pub fn load(path: &Path, timezone: &Timezone) -> Option<DateTime<FixedOffset>> {
+    let file = File::open(path).ok()?;
+
+    let exif = match Reader::new().read_from_container(&mut BufReader::new(file)) {
+        Ok(exif) => exif,
+        Err(err) => {
+            log::debug!("No EXIF data in {}: {}", path.display(), err);
+            return None;
+        }
+    };
+
+    DATE_TAGS
+        .iter()
+        .find_map(|(date_tag, offset_tag)| date(&exif, *date_tag, *offset_tag, timezone))
+}
+
+fn date(
+    exif: &Exif,
+    date_tag: Tag,
+    offset_tag: Tag,
+    timezone: &Timezone,
+) -> Option<DateTime<FixedOffset>> {
+    let mut date = ::exif::DateTime::from_ascii(ascii(exif, date_tag)?).ok()?;
+
+    if let Some(offset) = ascii(exif, offset_tag) {
+        // A malformed offset leaves the date in the configured timezone
+        let _ = date.parse_offset(offset);
+    }
+
+    let naive = NaiveDate::from_ymd_opt(date.year.into(), date.month.into(), date.day.into())?
+        .and_hms_opt(date.hour.into(), date.minute.into(), date.second.into())?;
+
+    match date.offset {
+        Some(minutes) => {
+            let offset = FixedOffset::east_opt(i32::from(minutes) * 60)?;
+            let date = naive.and_local_timezone(offset).single()?;
+            Some(timezone.convert(date.to_utc()))
+        }
+        None => timezone.localize(naive),
+    }
+}
+
+fn ascii(exif: &Exif, tag: Tag) -> Option<&[u8]> {
+    match &exif.get_field(tag, In::PRIMARY)?.value {
+        Value::Ascii(values) => values.first().map(Vec::as_slice),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::exif::Field;
+    use ::exif::experimental::Writer;
+    use std::env;
+    use std::fs;
+    use std::io::Cursor;
+    use std::path::PathBuf;
+
+    /// Writes a JPEG holding only the given EXIF ASCII fields.
+    fn write_jpeg(name: &str, values: &[(Tag, &str)]) -> PathBuf {
+        let fields: Vec<Field> = values
+            .iter()
+            .map(|(tag, value)| Field {
+                tag: *tag,
+                ifd_num: In::PRIMARY,
+                value: Value::Ascii(vec![value.as_bytes().to_vec()]),
+            })
+            .collect();
+
+        let mut writer = Writer::new();
+        for field in &fields {
+            writer.push_field(field);
+        }
+
+        let mut tiff = Cursor::new(Vec::new());
+        writer.write(&mut tiff, false).unwrap();
+        let tiff = tiff.into_inner();
+
+        let mut jpeg = vec![0xFF, 0xD8, 0xFF, 0xE1];
+        jpeg.extend_from_slice(&(tiff.len() as u16 + 8).to_be_bytes());
+        jpeg.extend_from_slice(b"Exif\0\0");
+        jpeg.extend_from_slice(&tiff);
+        jpeg.extend_from_slice(&[0xFF, 0xD9]);
+
+        let dir = env::temp_dir().join("autodate-exif");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        fs::write(&path, jpeg).unwrap();
+        path
+    }
+
+    fn utc() -> Timezone {
+        Timezone::Fixed(FixedOffset::east_opt(0).unwrap())
+    }
+
+    #[test]
+    fn prefers_the_original_date() {
+        let path = write_jpeg(
+            "original.jpg",
+            &[
+                (Tag::DateTime, "2025:11:05 09:00:00"),
+                (Tag::DateTimeOriginal, "2025:11:03 10:30:00"),
+            ],
+        );
+
+        let date = load(&path, &utc()).unwrap();
+        assert_eq!(date.to_rfc3339(), "2025-11-03T10:30:00+00:00");
+    }
+
+    #[test]
+    fn applies_the_recorded_offset() {
+        let path = write_jpeg(
+            "offset.jpg",
+            &[
+                (Tag::DateTimeOriginal, "2025:11:03 10:30:00"),
+                (Tag::OffsetTimeOriginal, "-05:00"),
+            ],
+        );
+
+        let date = load(&path, &utc()).unwrap();
+        assert_eq!(date.to_rfc3339(), "2025-11-03T15:30:00+00:00");
+
+        // A malformed offset leaves the date in the configured timezone
+        let path = write_jpeg(
+            "bad-offset.jpg",
+            &[
+                (Tag::DateTimeOriginal, "2025:11:03 10:30:00"),
+                (Tag::OffsetTimeOriginal, "soon"),
+            ],
+        );
+
+        let date = load(&path, &utc()).unwrap();
+        assert_eq!(date.to_rfc3339(), "2025-11-03T10:30:00+00:00");
+    }
+
+    #[test]
+    fn ignores_files_without_dates() {
+        let path = write_jpeg("empty.jpg", &[(Tag::Make, "Scanner")]);
+        assert_eq!(load(&path, &utc()), None);
+
+        let dir = env::temp_dir().join("autodate-exif");
+        let path = dir.join("plain.jpg");
+        fs::write(&path, [0xFF, 0xD8, 0xFF, 0xD9]).unwrap();
+        assert_eq!(load(&path, &utc()), None);
+    }
+}
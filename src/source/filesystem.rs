@@ -0,0 +1,48 @@
+use crate::date::formatter::DateFormatter;
+use crate::date::search::find_date;
+use crate::date::timezone::Timezone;
+use chrono::{DateTime, FixedOffset, NaiveTime, Utc};
+use regex::Regex;
+use std::fs;
+use std::path::Path;
+use std::time::SystemTime;
+
+/// Dates that come from the file itself rather than its content.
+#[derive(Debug, Default)]
+pub struct FileDates {
+    /// Date written in the original file name.
+    pub filename: Option<DateTime<FixedOffset>>,
+    pub modified: Option<DateTime<FixedOffset>>,
+    pub created: Option<DateTime<FixedOffset>>,
+}
+
+impl FileDates {
+    /// Searches the file name with `patterns` when given, and reads the
+    /// timestamps of the file.
+    pub fn load(
+        path: &Path,
+        patterns: Option<&[Regex]>,
+        formatter: &DateFormatter,
+        timezone: &Timezone,
+    ) -> Self {
+        let filename = patterns.and_then(|patterns| {
+            let name = path.file_name()?.to_string_lossy();
+            find_date(&name, patterns, formatter)
+                .and_then(|date| timezone.localize(date.and_time(NaiveTime::MIN)))
+        });
+
+        let metadata = fs::metadata(path).ok();
+        let timestamp = |time: std::io::Result<SystemTime>| {
+            time.ok()
+                .map(|time| timezone.convert(DateTime::<Utc>::from(time)))
+        };
+
+        Self {
+            filename,
+            modified: metadata
+                .as_ref()
+                .and_then(|metadata| timestamp(metadata.modified())),
+            created: metadata.and_then(|metadata| timestamp(metadata.created())),
+        }
+    }
+}
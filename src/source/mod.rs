@@ -0,0 +1,178 @@
+pub mod email;
+pub mod exif;
+pub mod filesystem;
+pub mod pdf;
+pub mod ubl;
+pub mod vendor;
+
+use crate::config::AppConfig;
+use crate::date::formatter::DateFormatter;
+use crate::date::timezone::Timezone;
+use crate::file::sniffer::FileType;
+use crate::file::template::Fields;
+use crate::source::email::Email;
+use crate::source::filesystem::FileDates;
+use crate::source::pdf::PdfContent;
+use crate::source::ubl::UblInvoice;
+use crate::source::vendor::Clues;
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Where the date used in a file name comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DateSource {
+    /// `IssueDate` of a UBL electronic invoice.
+    Ubl,
+    /// Creation date in the PDF info dictionary or XMP metadata.
+    Pdf,
+    /// Date found in the text of the first page of a PDF.
+    PdfText,
+    /// `DateTimeOriginal` in the EXIF data of a photo.
+    Exif,
+    /// `Date` header of a saved email.
+    Email,
+    /// Date written in the original file name.
+    Filename,
+    /// Last modification time of the file.
+    Mtime,
+    /// Creation time of the file.
+    Created,
+    /// The moment the file is processed.
+    Now,
+}
+
+impl DateSource {
+    pub fn as_str(&self) -> &str {
+        match self {
+            DateSource::Ubl => "ubl",
+            DateSource::Pdf => "pdf",
+            DateSource::PdfText => "pdf_text",
+            DateSource::Exif => "exif",
+            DateSource::Email => "email",
+            DateSource::Filename => "filename",
+            DateSource::Mtime => "mtime",
+            DateSource::Created => "created",
+            DateSource::Now => "now",
+        }
+    }
+}
+
+/// What could be read from a file and its content.
+pub struct Document {
+    file: FileDates,
+    ubl: Option<UblInvoice>,
+    pdf: Option<PdfContent>,
+    exif: Option<DateTime<FixedOffset>>,
+    email: Option<Email>,
+    vendor: Option<String>,
+}
+
+impl Document {
+    /// Parses the file according to its type, skipping the work no
+    /// configured date source needs.
+    pub fn load(path: &Path, file_type: Option<FileType>, config: &AppConfig) -> Self {
+        let formatter =
+            DateFormatter::new(config.locale, config.name_case, config.fiscal_year_start);
+
+        let patterns = config
+            .uses_source(DateSource::Filename)
+            .then_some(config.filename_date_patterns.as_slice());
+        let file = FileDates::load(path, patterns, &formatter, &config.timezone);
+
+        let ubl = match file_type {
+            Some(FileType::Xml) => UblInvoice::load(path),
+            _ => None,
+        };
+
+        let wants_pdf_text = config.vendors.needs_pdf_text();
+        let wants_pdf = wants_pdf_text
+            || config.uses_source(DateSource::Pdf)
+            || config.uses_source(DateSource::PdfText);
+
+        let pdf = match file_type {
+            Some(FileType::Pdf) if wants_pdf => {
+                let patterns = config
+                    .uses_source(DateSource::PdfText)
+                    .then_some(config.pdf_date_patterns.as_slice());
+
+                PdfContent::load(path, patterns, wants_pdf_text, &formatter, &config.timezone)
+            }
+            _ => None,
+        };
+
+        let exif = match file_type {
+            Some(FileType::Jpeg | FileType::Png | FileType::Heic)
+                if config.uses_source(DateSource::Exif) =>
+            {
+                exif::load(path, &config.timezone)
+            }
+            _ => None,
+        };
+
+        let email = match file_type {
+            Some(FileType::Eml) => Email::load(path, &config.timezone, config.max_unpacked_size),
+            _ => None,
+        };
+
+        let clues = Clues {
+            file_name: &path
+                .file_name()
+                .map(|name| name.to_string_lossy())
+                .unwrap_or_default(),
+            supplier_nit: ubl.as_ref().and_then(|ubl| ubl.supplier_nit.as_deref()),
+            sender_domain: email
+                .as_ref()
+                .and_then(|email| email.sender_domain.as_deref()),
+            pdf_text: pdf.as_ref().and_then(|pdf| pdf.first_page.as_deref()),
+        };
+
+        let vendor = config
+            .vendors
+            .detect(&clues)
+            .map(|vendor| vendor.name.clone());
+
+        Self {
+            file,
+            ubl,
+            pdf,
+            exif,
+            email,
+            vendor,
+        }
+    }
+
+    pub fn fields(&self) -> Fields {
+        let mut fields = Fields::new();
+
+        if let Some(ubl) = &self.ubl {
+            fields.extend(ubl.fields());
+        }
+
+        if let Some(email) = &self.email {
+            fields.extend(email.fields());
+        }
+
+        if let Some(vendor) = &self.vendor {
+            fields.insert("vendor".to_string(), vendor.clone());
+        }
+
+        fields
+    }
+
+    /// Returns the date this document has for `source`, if any.
+    pub fn date(&self, source: DateSource, timezone: &Timezone) -> Option<DateTime<FixedOffset>> {
+        match source {
+            DateSource::Ubl => self.ubl.as_ref().and_then(|ubl| ubl.date(timezone)),
+            DateSource::Pdf => self.pdf.as_ref().and_then(|pdf| pdf.metadata),
+            DateSource::PdfText => self.pdf.as_ref().and_then(|pdf| pdf.text),
+            DateSource::Exif => self.exif,
+            DateSource::Email => self.email.as_ref().and_then(|email| email.date),
+            DateSource::Filename => self.file.filename,
+            DateSource::Mtime => self.file.modified,
+            DateSource::Created => self.file.created,
+            DateSource::Now => Some(timezone.now()),
+        }
+    }
+}
@@ -0,0 +1,212 @@
+use crate::date::formatter::DateFormatter;
+use crate::date::search::find_date;
+use crate::date::timezone::Timezone;
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime};
+use lopdf::{Document, Object};
+use regex::Regex;
+use std::fs;
+use std::path::Path;
+use std::sync::LazyLock;
+
+/// Larger files are not parsed.
+const MAX_PDF_SIZE: u64 = 50 * 1024 * 1024;
+
+static XMP_CREATE_DATE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"xmp:CreateDate\s*(?:>|=\s*")\s*([^<"]+)"#).expect("Valid XMP date pattern")
+});
+
+/// What was read from a PDF document.
+#[derive(Debug, Default)]
+pub struct PdfContent {
+    /// `/CreationDate` of the info dictionary, or `xmp:CreateDate`.
+    pub metadata: Option<DateTime<FixedOffset>>,
+    /// First date on page one matched by the configured patterns.
+    pub text: Option<DateTime<FixedOffset>>,
+    /// Text of the first page, when asked to keep it.
+    pub first_page: Option<String>,
+}
+
+impl PdfContent {
+    /// Reads the metadata dates and, when `patterns` is given, searches the
+    /// text of the first page with them.
+    pub fn load(
+        path: &Path,
+        patterns: Option<&[Regex]>,
+        keep_text: bool,
+        formatter: &DateFormatter,
+        timezone: &Timezone,
+    ) -> Option<Self> {
+        if fs::metadata(path).ok()?.len() > MAX_PDF_SIZE {
+            log::debug!("PDF file too large to parse: {}", path.display());
+            return None;
+        }
+
+        let document = match Document::load(path) {
+            Ok(document) => document,
+            Err(err) => {
+                log::warn!("Failed to parse PDF {}: {}", path.display(), err);
+                return None;
+            }
+        };
+
+        let metadata = info_date(&document)
+            .and_then(|date| parse_pdf_date(&date, timezone))
+            .or_else(|| xmp_date(&document).and_then(|date| parse_xmp_date(&date, timezone)));
+
+        let first_page = (patterns.is_some() || keep_text)
+            .then(|| document.extract_text(&[1]).ok())
+            .flatten();
+
+        let text = patterns
+            .zip(first_page.as_deref())
+            .and_then(|(patterns, text)| {
+                find_date(text, patterns, formatter)
+                    .and_then(|date| timezone.localize(date.and_time(NaiveTime::MIN)))
+            });
+
+        Some(Self {
+            metadata,
+            text,
+            first_page: first_page.filter(|_| keep_text),
+        })
+    }
+}
+
+fn info_date(document: &Document) -> Option<String> {
+    let info = document.trailer.get(b"Info").ok()?;
+    let (_, info) = document.dereference(info).ok()?;
+    let date = info.as_dict().ok()?.get(b"CreationDate").ok()?;
+    let (_, date) = document.dereference(date).ok()?;
+
+    Some(String::from_utf8_lossy(date.as_str().ok()?).to_string())
+}
+
+fn xmp_date(document: &Document) -> Option<String> {
+    let metadata = document.catalog().ok()?.get(b"Metadata").ok()?;
+    let (_, metadata) = document.dereference(metadata).ok()?;
+
+    let Object::Stream(stream) = metadata else {
+        return None;
+    };
+
+    let content = stream
+        .decompressed_content()
+        .unwrap_or_else(|_| stream.content.clone());
+    let xml = String::from_utf8_lossy(&content);
+
+    Some(XMP_CREATE_DATE.captures(&xml)?[1].trim().to_string())
+}
+
+/// Parses a PDF date string, `D:YYYYMMDDHHmmSSOHH'mm'`, where everything
+/// after the year is optional.
+fn parse_pdf_date(value: &str, timezone: &Timezone) -> Option<DateTime<FixedOffset>> {
+    let value = value.trim().trim_start_matches("D:");
+    let digits: String = value.chars().take_while(char::is_ascii_digit).collect();
+
+    if digits.len() < 4 {
+        return None;
+    }
+
+    let part = |start: usize, end: usize, default: u32| {
+        digits
+            .get(start..end)
+            .and_then(|part| part.parse().ok())
+            .unwrap_or(default)
+    };
+
+    let date = NaiveDate::from_ymd_opt(digits[..4].parse().ok()?, part(4, 6, 1), part(6, 8, 1))?
+        .and_hms_opt(part(8, 10, 0), part(10, 12, 0), part(12, 14, 0))?;
+
+    let offset = &value[digits.len()..];
+
+    match offset.chars().next() {
+        Some('Z') => Some(timezone.convert(date.and_utc())),
+        Some(sign @ ('+' | '-')) => {
+            let offset: String = offset[1..].chars().filter(char::is_ascii_digit).collect();
+            let hours: i32 = offset.get(..2)?.parse().ok()?;
+            let minutes: i32 = offset.get(2..4).and_then(|m| m.parse().ok()).unwrap_or(0);
+            let seconds = (hours * 3600 + minutes * 60) * if sign == '-' { -1 } else { 1 };
+
+            let date = date
+                .and_local_timezone(FixedOffset::east_opt(seconds)?)
+                .single()?;
+            Some(timezone.convert(date.to_utc()))
+        }
+        _ => timezone.localize(date),
+    }
+}
+
+fn parse_xmp_date(value: &str, timezone: &Timezone) -> Option<DateTime<FixedOffset>> {
+    if let Ok(date) = DateTime::parse_from_rfc3339(value)
+        .or_else(|_| DateTime::parse_from_str(value, "%Y-%m-%dT%H:%M%:z"))
+    {
+        return Some(timezone.convert(date.to_utc()));
+    }
+
+    let date = NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f")
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M"))
+        .or_else(|_| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d").map(|date| date.and_time(NaiveTime::MIN))
+        })
+        .ok()?;
+
+    timezone.localize(date)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc() -> Timezone {
+        Timezone::Fixed(FixedOffset::east_opt(0).unwrap())
+    }
+
+    fn at(value: &str) -> Option<String> {
+        parse_pdf_date(value, &utc()).map(|date| date.to_rfc3339())
+    }
+
+    #[test]
+    fn parses_full_dates() {
+        assert_eq!(
+            at("D:20251019103000-05'00'").as_deref(),
+            Some("2025-10-19T15:30:00+00:00")
+        );
+        assert_eq!(
+            at("D:20251019103000+05'30").as_deref(),
+            Some("2025-10-19T05:00:00+00:00")
+        );
+        assert_eq!(
+            at("D:20251019103000Z").as_deref(),
+            Some("2025-10-19T10:30:00+00:00")
+        );
+    }
+
+    #[test]
+    fn fills_in_missing_parts() {
+        assert_eq!(at("D:2025").as_deref(), Some("2025-01-01T00:00:00+00:00"));
+        assert_eq!(at("D:202510").as_deref(), Some("2025-10-01T00:00:00+00:00"));
+        assert_eq!(
+            at(" 20251019").as_deref(),
+            Some("2025-10-19T00:00:00+00:00")
+        );
+    }
+
+    #[test]
+    fn reads_dates_without_offset_in_the_timezone() {
+        let bogota = Timezone::Fixed(FixedOffset::west_opt(5 * 3600).unwrap());
+
+        assert_eq!(
+            parse_pdf_date("D:20251019103000", &bogota).map(|date| date.to_rfc3339()),
+            Some("2025-10-19T10:30:00-05:00".to_string())
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_dates() {
+        assert_eq!(at(""), None);
+        assert_eq!(at("D:"), None);
+        assert_eq!(at("D:202"), None);
+        assert_eq!(at("D:20251319"), None);
+        assert_eq!(at("D:20251019103000+5"), None);
+    }
+}
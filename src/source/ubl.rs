@@ -0,0 +1,291 @@
+use crate::date::timezone::Timezone;
+use crate::file::template::Fields;
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveTime, Utc};
+use roxmltree::{Document, Node, ParsingOptions};
+use std::fs;
+use std::path::Path;
+
+/// Larger files are not treated as invoices.
+const MAX_XML_SIZE: u64 = 20 * 1024 * 1024;
+
+const INVOICE_ROOTS: &[&str] = &["Invoice", "CreditNote", "DebitNote"];
+
+const ATTACHED_DOCUMENT_ROOT: &str = "AttachedDocument";
+
+/// The parts of a UBL 2.1 invoice, as issued for DIAN electronic
+/// invoicing, that are used for naming.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UblInvoice {
+    pub issue_date: NaiveDate,
+    pub issue_time: Option<String>,
+    pub invoice_number: Option<String>,
+    pub supplier_nit: Option<String>,
+    pub supplier_name: Option<String>,
+    pub total: Option<String>,
+    pub currency: Option<String>,
+}
+
+impl UblInvoice {
+    pub fn load(path: &Path) -> Option<Self> {
+        if fs::metadata(path).ok()?.len() > MAX_XML_SIZE {
+            log::debug!("XML file too large to parse: {}", path.display());
+            return None;
+        }
+
+        let xml = fs::read_to_string(path).ok()?;
+
+        match Self::parse(&xml) {
+            Ok(invoice) => invoice,
+            Err(err) => {
+                log::warn!("Failed to parse XML {}: {}", path.display(), err);
+                None
+            }
+        }
+    }
+
+    /// Parses an `Invoice`, `CreditNote` or `DebitNote`, or the invoice
+    /// embedded in a DIAN `AttachedDocument`. Other XML yields `None`.
+    pub fn parse(xml: &str) -> Result<Option<Self>, roxmltree::Error> {
+        let document = parse_document(xml)?;
+        let root = document.root_element();
+        let root_name = root.tag_name().name();
+
+        if INVOICE_ROOTS.contains(&root_name) {
+            return Ok(Self::from_invoice(root));
+        }
+
+        if root_name != ATTACHED_DOCUMENT_ROOT {
+            return Ok(None);
+        }
+
+        let embedded =
+            text(root, &["Attachment", "ExternalReference", "Description"]).and_then(|xml| {
+                let document = parse_document(&xml).ok()?;
+                let root = document.root_element();
+                INVOICE_ROOTS
+                    .contains(&root.tag_name().name())
+                    .then(|| Self::from_invoice(root))
+                    .flatten()
+            });
+
+        Ok(embedded.or_else(|| Self::from_attached_document(root)))
+    }
+
+    fn from_invoice(root: Node) -> Option<Self> {
+        let supplier = path(root, &["AccountingSupplierParty", "Party"]);
+        let total = path(root, &["LegalMonetaryTotal", "PayableAmount"])
+            .or_else(|| path(root, &["RequestedMonetaryTotal", "PayableAmount"]));
+
+        Some(Self {
+            issue_date: parse_date(&text(root, &["IssueDate"])?)?,
+            issue_time: text(root, &["IssueTime"]),
+            invoice_number: text(root, &["ID"]),
+            supplier_nit: supplier.and_then(party_nit),
+            supplier_name: supplier.and_then(party_name),
+            total: total.and_then(node_text),
+            currency: total
+                .and_then(|total| total.attribute("currencyID"))
+                .map(String::from)
+                .or_else(|| text(root, &["DocumentCurrencyCode"])),
+        })
+    }
+
+    /// Falls back to the envelope when the embedded invoice is missing.
+    fn from_attached_document(root: Node) -> Option<Self> {
+        let sender = path(root, &["SenderParty"]);
+
+        Some(Self {
+            issue_date: parse_date(&text(root, &["IssueDate"])?)?,
+            issue_time: text(root, &["IssueTime"]),
+            invoice_number: text(root, &["ParentDocumentID"]),
+            supplier_nit: sender.and_then(party_nit),
+            supplier_name: sender.and_then(party_name),
+            total: None,
+            currency: None,
+        })
+    }
+
+    /// Issue date and time in `timezone`. Times without an offset are
+    /// taken as wall time in `timezone`.
+    pub fn date(&self, timezone: &Timezone) -> Option<DateTime<FixedOffset>> {
+        let time = self.issue_time.as_deref().unwrap_or("00:00:00");
+        let date_time = format!("{}T{}", self.issue_date, time);
+
+        if let Ok(date) = DateTime::parse_from_rfc3339(&date_time) {
+            return Some(timezone.convert(date.with_timezone(&Utc)));
+        }
+
+        let time = NaiveTime::parse_from_str(time, "%H:%M:%S%.f").unwrap_or_default();
+        timezone.localize(self.issue_date.and_time(time))
+    }
+
+    pub fn fields(&self) -> Fields {
+        [
+            ("invoice_number", &self.invoice_number),
+            ("supplier_nit", &self.supplier_nit),
+            ("supplier_name", &self.supplier_name),
+            ("total", &self.total),
+            ("currency", &self.currency),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| Some((name.to_string(), value.clone()?)))
+        .collect()
+    }
+}
+
+fn parse_document(xml: &str) -> Result<Document<'_>, roxmltree::Error> {
+    let options = ParsingOptions {
+        allow_dtd: true,
+        ..ParsingOptions::default()
+    };
+
+    Document::parse_with_options(xml, options)
+}
+
+fn parse_date(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()
+}
+
+fn party_nit(party: Node) -> Option<String> {
+    text(party, &["PartyTaxScheme", "CompanyID"])
+        .or_else(|| text(party, &["PartyLegalEntity", "CompanyID"]))
+        .or_else(|| text(party, &["PartyIdentification", "ID"]))
+}
+
+fn party_name(party: Node) -> Option<String> {
+    text(party, &["PartyTaxScheme", "RegistrationName"])
+        .or_else(|| text(party, &["PartyLegalEntity", "RegistrationName"]))
+        .or_else(|| text(party, &["PartyName", "Name"]))
+}
+
+/// Follows child elements by local name, ignoring namespace prefixes.
+fn path<'a, 'input>(node: Node<'a, 'input>, names: &[&str]) -> Option<Node<'a, 'input>> {
+    names.iter().try_fold(node, |node, name| {
+        node.children()
+            .find(|child| child.is_element() && child.tag_name().name() == *name)
+    })
+}
+
+fn text(node: Node, names: &[&str]) -> Option<String> {
+    path(node, names).and_then(node_text)
+}
+
+fn node_text(node: Node) -> Option<String> {
+    node.text()
+        .map(str::trim)
+        .filter(|text| !text.is_empty())
+        .map(String::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INVOICE: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Invoice xmlns="urn:oasis:names:specification:ubl:schema:xsd:Invoice-2"
+    xmlns:cac="urn:oasis:names:specification:ubl:schema:xsd:CommonAggregateComponents-2"
+    xmlns:cbc="urn:oasis:names:specification:ubl:schema:xsd:CommonBasicComponents-2">
+  <cbc:ID>SETP990000002</cbc:ID>
+  <cbc:IssueDate>2025-11-03</cbc:IssueDate>
+  <cbc:IssueTime>10:30:00-05:00</cbc:IssueTime>
+  <cbc:DocumentCurrencyCode>COP</cbc:DocumentCurrencyCode>
+  <cac:AccountingSupplierParty>
+    <cac:Party>
+      <cac:PartyTaxScheme>
+        <cbc:RegistrationName>ACME S.A.S.</cbc:RegistrationName>
+        <cbc:CompanyID>900123456</cbc:CompanyID>
+      </cac:PartyTaxScheme>
+    </cac:Party>
+  </cac:AccountingSupplierParty>
+  <cac:LegalMonetaryTotal>
+    <cbc:PayableAmount currencyID="COP">119000.00</cbc:PayableAmount>
+  </cac:LegalMonetaryTotal>
+</Invoice>"#;
+
+    fn attached(description: &str) -> String {
+        format!(
+            r#"<AttachedDocument xmlns="urn:oasis:names:specification:ubl:schema:xsd:AttachedDocument-2"
+    xmlns:cac="urn:oasis:names:specification:ubl:schema:xsd:CommonAggregateComponents-2"
+    xmlns:cbc="urn:oasis:names:specification:ubl:schema:xsd:CommonBasicComponents-2">
+  <cbc:IssueDate>2025-11-04</cbc:IssueDate>
+  <cbc:ParentDocumentID>FE-77</cbc:ParentDocumentID>
+  <cac:SenderParty>
+    <cac:PartyTaxScheme>
+      <cbc:RegistrationName>Sender Ltda</cbc:RegistrationName>
+      <cbc:CompanyID>800999888</cbc:CompanyID>
+    </cac:PartyTaxScheme>
+  </cac:SenderParty>
+  <cac:Attachment>
+    <cac:ExternalReference>
+      <cbc:Description>{}</cbc:Description>
+    </cac:ExternalReference>
+  </cac:Attachment>
+</AttachedDocument>"#,
+            description
+        )
+    }
+
+    #[test]
+    fn reads_invoices() {
+        let invoice = UblInvoice::parse(INVOICE).unwrap().unwrap();
+
+        assert_eq!(
+            invoice.issue_date,
+            NaiveDate::from_ymd_opt(2025, 11, 3).unwrap()
+        );
+        assert_eq!(invoice.invoice_number.as_deref(), Some("SETP990000002"));
+        assert_eq!(invoice.supplier_nit.as_deref(), Some("900123456"));
+        assert_eq!(invoice.supplier_name.as_deref(), Some("ACME S.A.S."));
+        assert_eq!(invoice.total.as_deref(), Some("119000.00"));
+        assert_eq!(invoice.currency.as_deref(), Some("COP"));
+    }
+
+    #[test]
+    fn reads_the_invoice_embedded_in_attached_documents() {
+        let embedded = INVOICE
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;");
+        let invoice = UblInvoice::parse(&attached(&embedded)).unwrap().unwrap();
+        assert_eq!(invoice.invoice_number.as_deref(), Some("SETP990000002"));
+
+        // Without an embedded invoice, the envelope is used
+        let invoice = UblInvoice::parse(&attached("")).unwrap().unwrap();
+        assert_eq!(
+            invoice.issue_date,
+            NaiveDate::from_ymd_opt(2025, 11, 4).unwrap()
+        );
+        assert_eq!(invoice.invoice_number.as_deref(), Some("FE-77"));
+        assert_eq!(invoice.supplier_nit.as_deref(), Some("800999888"));
+        assert_eq!(invoice.total, None);
+    }
+
+    #[test]
+    fn ignores_other_xml() {
+        assert_eq!(
+            UblInvoice::parse("<Order><IssueDate>2025-11-03</IssueDate></Order>").unwrap(),
+            None
+        );
+        assert!(UblInvoice::parse("<Invoice>").is_err());
+    }
+
+    #[test]
+    fn dates_in_the_configured_timezone() {
+        let invoice = UblInvoice::parse(INVOICE).unwrap().unwrap();
+        let utc = Timezone::Fixed(FixedOffset::east_opt(0).unwrap());
+
+        assert_eq!(
+            invoice.date(&utc).unwrap().to_rfc3339(),
+            "2025-11-03T15:30:00+00:00"
+        );
+
+        let without_offset = UblInvoice {
+            issue_time: Some("10:30:00".to_string()),
+            ..invoice
+        };
+        assert_eq!(
+            without_offset.date(&utc).unwrap().to_rfc3339(),
+            "2025-11-03T10:30:00+00:00"
+        );
+    }
+}
@@ -0,0 +1,98 @@
+use crate::toml_file::{self, TomlFileError, deserialize_regexes};
+use regex::Regex;
+use serde::Deserialize;
+use std::path::Path;
+
+/// Known vendors, read from `VENDORS_FILE`. The first vendor with a
+/// matching identifier is the one a file belongs to.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct Vendors {
+    #[serde(default, rename = "vendor")]
+    vendors: Vec<Vendor>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Vendor {
+    /// Canonical name, used for `{vendor}`.
+    pub name: String,
+    /// Supplier tax IDs (NIT) in UBL invoices.
+    #[serde(default)]
+    nits: Vec<String>,
+    /// Sender email domains.
+    #[serde(default)]
+    domains: Vec<String>,
+    /// Regexes searched in the file name.
+    #[serde(default, deserialize_with = "deserialize_regexes")]
+    filenames: Vec<Regex>,
+    /// Text found on the first page of a PDF, ignoring case.
+    #[serde(default)]
+    pdf_text: Vec<String>,
+}
+
+/// What a vendor is recognised by.
+pub struct Clues<'a> {
+    pub file_name: &'a str,
+    pub supplier_nit: Option<&'a str>,
+    pub sender_domain: Option<&'a str>,
+    pub pdf_text: Option<&'a str>,
+}
+
+impl Vendors {
+    pub fn load(path: &Path) -> Result<Self, TomlFileError> {
+        toml_file::load(path, "vendors")
+    }
+
+    /// Whether any vendor is recognised by PDF text, which is only
+    /// extracted when needed.
+    pub fn needs_pdf_text(&self) -> bool {
+        self.vendors
+            .iter()
+            .any(|vendor| !vendor.pdf_text.is_empty())
+    }
+
+    pub fn detect(&self, clues: &Clues) -> Option<&Vendor> {
+        self.vendors.iter().find(|vendor| vendor.matches(clues))
+    }
+}
+
+impl Vendor {
+    fn matches(&self, clues: &Clues) -> bool {
+        let nit = clues.supplier_nit.map(normalize_nit);
+
+        if nit.is_some_and(|nit| self.nits.iter().any(|known| normalize_nit(known) == nit)) {
+            return true;
+        }
+
+        if clues.sender_domain.is_some_and(|sender| {
+            self.domains.iter().any(|domain| {
+                let domain = domain.to_lowercase();
+                sender == domain || sender.ends_with(&format!(".{}", domain))
+            })
+        }) {
+            return true;
+        }
+
+        if self
+            .filenames
+            .iter()
+            .any(|pattern| pattern.is_match(clues.file_name))
+        {
+            return true;
+        }
+
+        clues.pdf_text.is_some_and(|text| {
+            let text = text.to_lowercase();
+            self.pdf_text
+                .iter()
+                .any(|snippet| text.contains(&snippet.to_lowercase()))
+        })
+    }
+}
+
+/// NITs are compared by their digits before the check digit, so
+/// `900.123.456-7` and `900123456` are the same vendor.
+fn normalize_nit(nit: &str) -> String {
+    let nit = nit.split('-').next().unwrap_or(nit);
+    nit.chars().filter(char::is_ascii_digit).collect()
+}
@@ -0,0 +1,334 @@
+use crate::lock_file;
+use crate::source::DateSource;
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Version of the records written by this build. Stores written by a newer
+/// version are refused rather than misread.
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Error, Debug)]
+pub enum StateError {
+    #[error("Failed to access state file {0}: {1}")]
+    IoError(String, io::Error),
+
+    #[error("State file {0} has schema version {1}, this version reads up to {2}")]
+    UnsupportedSchema(String, u32, u32),
+
+    #[error("State file {0} has no schema header")]
+    MissingHeader(String),
+}
+
+/// What happened to a processed file.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Renamed,
+    Quarantined,
+    Failed,
+    /// Left as it is. Only written to the audit log.
+    Skipped,
+}
+
+/// One processed file.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Record {
+    pub time: DateTime<FixedOffset>,
+    pub original: PathBuf,
+    pub path: PathBuf,
+    /// SHA-256 of the content, in hex.
+    pub hash: Option<String>,
+    pub size: Option<u64>,
+    pub source: Option<DateSource>,
+    pub outcome: Outcome,
+    pub error: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct Header {
+    schema: u32,
+}
+
+/// Journal of every processed file, kept in `STATE_FILE` as a schema header
+/// followed by one JSON record per line. Records are only ever appended and
+/// synced, so a crash can at most leave a partial last line, which is
+/// skipped when reading. Appending and compacting take the lock file next
+/// to the store, so a compaction never drops a record another instance
+/// appends meanwhile.
+pub struct StateStore {
+    path: PathBuf,
+}
+
+impl StateStore {
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+        }
+    }
+
+    pub fn append(&self, record: &Record) -> Result<(), StateError> {
+        let _lock = lock_file::lock(&self.path).map_err(|err| self.io_error(err))?;
+        self.write_record(record).map_err(|err| self.io_error(err))
+    }
+
+    /// Reads every record, skipping lines that cannot be parsed. A missing
+    /// file has no records.
+    pub fn records(&self) -> Result<Vec<Record>, StateError> {
+        let file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(self.io_error(err)),
+        };
+
+        let mut lines = BufReader::new(file).lines();
+
+        let header = match lines.next() {
+            Some(line) => line.map_err(|err| self.io_error(err))?,
+            None => return Ok(Vec::new()),
+        };
+
+        let schema = serde_json::from_str::<Header>(&header)
+            .map_err(|_| StateError::MissingHeader(self.display()))?
+            .schema;
+
+        if schema > SCHEMA_VERSION {
+            return Err(StateError::UnsupportedSchema(
+                self.display(),
+                schema,
+                SCHEMA_VERSION,
+            ));
+        }
+
+        let mut records = Vec::new();
+
+        for (number, line) in lines.enumerate() {
+            let line = line.map_err(|err| self.io_error(err))?;
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            match serde_json::from_str(&line) {
+                Ok(record) => records.push(record),
+                Err(err) => log::warn!(
+                    "Skipping unreadable line {} of {}: {}",
+                    number + 2,
+                    self.path.display(),
+                    err
+                ),
+            }
+        }
+
+        Ok(records)
+    }
+
+    /// Rewrites the store with the current schema, keeping only the latest
+    /// record for each original file and content and dropping unreadable
+    /// lines. A name reused by another document keeps the history of both.
+    /// Returns how many records were kept.
+    pub fn compact(&self) -> Result<usize, StateError> {
+        let _lock = lock_file::lock(&self.path).map_err(|err| self.io_error(err))?;
+        let records = self.records()?;
+
+        let mut latest = HashMap::new();
+        for (index, record) in records.iter().enumerate() {
+            latest.insert((&record.original, &record.hash), index);
+        }
+
+        let kept: Vec<&Record> = records
+            .iter()
+            .enumerate()
+            .filter(|(index, record)| latest.get(&(&record.original, &record.hash)) == Some(index))
+            .map(|(_, record)| record)
+            .collect();
+
+        self.replace(&kept).map_err(|err| self.io_error(err))?;
+        Ok(kept.len())
+    }
+
+    fn write_record(&self, record: &Record) -> io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(&self.path)?;
+
+        let mut content = String::new();
+
+        if file.metadata()?.len() == 0 {
+            content.push_str(&header_line()?);
+        } else if !ends_with_newline(&mut file)? {
+            // Finish a line cut short by a crash so the record starts on its own
+            content.push('\n');
+        }
+
+        content.push_str(&serde_json::to_string(record).map_err(io::Error::other)?);
+        content.push('\n');
+
+        file.write_all(content.as_bytes())?;
+        file.sync_data()
+    }
+
+    /// Writes `records` to a temporary file and renames it over the store.
+    fn replace(&self, records: &[&Record]) -> io::Result<()> {
+        let temp = self.path.with_extension("tmp");
+        let mut file = File::create(&temp)?;
+
+        file.write_all(header_line()?.as_bytes())?;
+
+        for record in records {
+            let line = serde_json::to_string(record).map_err(io::Error::other)?;
+            file.write_all(line.as_bytes())?;
+            file.write_all(b"\n")?;
+        }
+
+        file.sync_all()?;
+        fs::rename(&temp, &self.path)
+    }
+
+    fn display(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    fn io_error(&self, err: io::Error) -> StateError {
+        StateError::IoError(self.display(), err)
+    }
+}
+
+/// SHA-256 and size of the file at `path`.
+pub fn fingerprint(path: &Path) -> io::Result<(String, u64)> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let size = io::copy(&mut file, &mut hasher)?;
+
+    let hash = hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+
+    Ok((hash, size))
+}
+
+fn header_line() -> io::Result<String> {
+    let header = Header {
+        schema: SCHEMA_VERSION,
+    };
+    let mut line = serde_json::to_string(&header).map_err(io::Error::other)?;
+    line.push('\n');
+    Ok(line)
+}
+
+fn ends_with_newline(file: &mut File) -> io::Result<bool> {
+    let mut last = [0];
+    file.seek(SeekFrom::End(-1))?;
+    file.read_exact(&mut last)?;
+    Ok(last[0] == b'\n')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::thread;
+
+    fn store(name: &str) -> StateStore {
+        let dir = env::temp_dir().join("autodate-state-tests");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("{}.jsonl", name));
+        let _ = fs::remove_file(&path);
+        StateStore::new(&path)
+    }
+
+    fn record(original: &str, path: &str, hash: &str, outcome: Outcome) -> Record {
+        Record {
+            time: DateTime::parse_from_rfc3339("2025-10-19T10:00:00-05:00").unwrap(),
+            original: PathBuf::from(original),
+            path: PathBuf::from(path),
+            hash: Some(hash.to_string()),
+            size: Some(1),
+            source: None,
+            outcome,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn reads_appended_records() {
+        let store = store("append");
+        assert!(store.records().unwrap().is_empty());
+
+        store
+            .append(&record("a.pdf", "2025-10.pdf", "1", Outcome::Renamed))
+            .unwrap();
+        fs::OpenOptions::new()
+            .append(true)
+            .open(&store.path)
+            .unwrap()
+            .write_all(b"{\"cut short")
+            .unwrap();
+        store
+            .append(&record("b.pdf", "2025-11.pdf", "2", Outcome::Renamed))
+            .unwrap();
+
+        let records = store.records().unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].original, PathBuf::from("b.pdf"));
+    }
+
+    #[test]
+    fn compacts_per_original_file_and_content() {
+        let store = store("compact");
+
+        store
+            .append(&record("a.pdf", "a.pdf", "1", Outcome::Failed))
+            .unwrap();
+        store
+            .append(&record("a.pdf", "2025-10.pdf", "1", Outcome::Renamed))
+            .unwrap();
+        // Another document later given the same name
+        store
+            .append(&record("b.pdf", "2025-10.pdf", "2", Outcome::Renamed))
+            .unwrap();
+
+        assert_eq!(store.compact().unwrap(), 2);
+
+        let records = store.records().unwrap();
+        assert_eq!(records[0].outcome, Outcome::Renamed);
+        assert_eq!(records[0].original, PathBuf::from("a.pdf"));
+        assert_eq!(records[1].original, PathBuf::from("b.pdf"));
+    }
+
+    #[test]
+    fn keeps_records_appended_while_compacting() {
+        let store = store("concurrent");
+        let path = store.path.clone();
+
+        let appender = thread::spawn(move || {
+            let store = StateStore::new(&path);
+            for n in 0..50 {
+                let name = format!("{}.pdf", n);
+                store
+                    .append(&record(&name, &name, &n.to_string(), Outcome::Renamed))
+                    .unwrap();
+            }
+        });
+
+        for _ in 0..20 {
+            store.compact().unwrap();
+        }
+        appender.join().unwrap();
+
+        assert_eq!(store.records().unwrap().len(), 50);
+    }
+}
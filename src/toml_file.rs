@@ -0,0 +1,49 @@
+use regex::Regex;
+use serde::de::{self, DeserializeOwned};
+use serde::{Deserialize, Deserializer};
+use std::fs;
+use std::path::Path;
+use thiserror::Error;
+
+/// Errors reading one of the TOML files the configuration points to, named
+/// as in "rules file C:\rules.toml".
+#[derive(Error, Debug)]
+pub enum TomlFileError {
+    #[error("Failed to read {0}: {1}")]
+    IoError(String, std::io::Error),
+
+    #[error("Invalid {0}: {1}")]
+    ParseError(String, toml::de::Error),
+}
+
+pub fn load<T: DeserializeOwned>(path: &Path, kind: &str) -> Result<T, TomlFileError> {
+    let display = format!("{} file {}", kind, path.display());
+
+    let content =
+        fs::read_to_string(path).map_err(|err| TomlFileError::IoError(display.clone(), err))?;
+
+    toml::from_str(&content).map_err(|err| TomlFileError::ParseError(display, err))
+}
+
+/// Compiles `pattern`, naming it in the error.
+pub fn compile_pattern<E: de::Error>(pattern: &str) -> Result<Regex, E> {
+    Regex::new(pattern).map_err(|err| E::custom(format!("invalid pattern `{}`: {}", pattern, err)))
+}
+
+pub fn deserialize_regex<'de, D>(deserializer: D) -> Result<Option<Regex>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let pattern = String::deserialize(deserializer)?;
+    compile_pattern(&pattern).map(Some)
+}
+
+pub fn deserialize_regexes<'de, D>(deserializer: D) -> Result<Vec<Regex>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|pattern| compile_pattern(pattern))
+        .collect()
+}
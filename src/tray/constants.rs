@@ -10,8 +10,15 @@ pub const MENU_RUNNING: &str = "Running";
 
 pub const MENU_DISABLED: &str = "Disabled (Click to re-enable)";
 
+pub const MENU_RELOAD: &str = "Reload config";
+
+pub const MENU_OPEN_LOG: &str = "Open log";
+
 pub const MENU_EXIT: &str = "Exit";
 
+/// Position of the warning item, below the running toggle.
+pub const MENU_WARNING_POSITION: usize = 2;
+
 pub const ICON_GREEN: u16 = 1;
 
 pub const ICON_RED: u16 = 2;
@@ -2,6 +2,9 @@
 pub enum TrayEvent {
     Title,
     Running,
+    Warning,
+    Reload,
+    OpenLog,
     Exit,
 }
 
@@ -10,6 +13,9 @@ impl From<&str> for TrayEvent {
         match s {
             "Title" => TrayEvent::Title,
             "Running" => TrayEvent::Running,
+            "Warning" => TrayEvent::Warning,
+            "Reload" => TrayEvent::Reload,
+            "OpenLog" => TrayEvent::OpenLog,
             "Exit" => TrayEvent::Exit,
             _ => TrayEvent::Title,
         }
@@ -21,6 +27,9 @@ impl TrayEvent {
         match self {
             TrayEvent::Title => "Title",
             TrayEvent::Running => "Running",
+            TrayEvent::Warning => "Warning",
+            TrayEvent::Reload => "Reload",
+            TrayEvent::OpenLog => "OpenLog",
             TrayEvent::Exit => "Exit",
         }
     }
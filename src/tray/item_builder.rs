@@ -40,6 +40,11 @@ impl TrayItemBuilder {
         self
     }
 
+    pub fn with_enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
     pub fn with_checked(mut self, checked: bool) -> Self {
         self.checked = checked;
         self
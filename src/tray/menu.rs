@@ -1,4 +1,6 @@
-use crate::tray::constants::{ICON_GREEN, MENU_EXIT, MENU_RUNNING, MENU_TITLE};
+use crate::tray::constants::{
+    ICON_GREEN, MENU_EXIT, MENU_OPEN_LOG, MENU_RELOAD, MENU_RUNNING, MENU_TITLE,
+};
 use crate::tray::events::TrayEvent;
 use crate::tray::item_builder::{TrayItemBuilder, TrayMenuItemType};
 use tray_icon::menu::{ContextMenu, IsMenuItem, Menu, MenuId};
@@ -35,6 +37,20 @@ impl Default for TrayMenu {
                 .build(TrayMenuItemType::Check),
         );
 
+        menu.add_item(
+            TrayItemBuilder::new()
+                .with_id(MenuId::new(TrayEvent::Reload.as_str()))
+                .with_title(MENU_RELOAD)
+                .build(TrayMenuItemType::Normal),
+        );
+
+        menu.add_item(
+            TrayItemBuilder::new()
+                .with_id(MenuId::new(TrayEvent::OpenLog.as_str()))
+                .with_title(MENU_OPEN_LOG)
+                .build(TrayMenuItemType::Normal),
+        );
+
         menu.add_item(
             TrayItemBuilder::new()
                 .with_id(MenuId::new(TrayEvent::Exit.as_str()))
@@ -64,6 +80,18 @@ impl TrayMenu {
         }
     }
 
+    pub fn insert_item(&mut self, item: Box<dyn IsMenuItem>, position: usize) {
+        self.menu
+            .insert(item.as_ref(), position)
+            .expect("Error inserting menu item");
+    }
+
+    pub fn remove_item(&mut self, id: &MenuId) {
+        if let Some(index) = self.menu.items().iter().position(|i| i.id() == id) {
+            self.menu.remove_at(index);
+        }
+    }
+
     pub fn as_context_menu(&self) -> Option<Box<dyn ContextMenu>> {
         Some(Box::new(self.menu.clone()))
     }
@@ -1,3 +1,4 @@
+use crate::config::{AppConfigError, show_error};
 use crate::tray::events::TrayEvent;
 use crate::tray::menu::TrayMenu;
 use crate::tray::tray::Tray;
@@ -10,39 +11,73 @@ use winit::event_loop::{ControlFlow, EventLoop, EventLoopProxy};
 #[derive(Debug, Clone)]
 pub enum UserEvent {
     MenuEvent(MenuEvent),
+    /// Shows a warning in the tray, or clears it with `None`.
+    Warning(Option<String>),
+    /// Sets the running toggle after the watcher was paused or resumed
+    /// elsewhere.
+    Running(bool),
+    /// Shows why a new configuration was not taken.
+    ConfigError(AppConfigError),
 }
 
 pub struct TrayRunner {
     tray: Arc<Mutex<Tray>>,
     menu: Arc<Mutex<TrayMenu>>,
+    event_loop: Option<EventLoop<UserEvent>>,
 }
 
 impl TrayRunner {
     pub fn new(tray: Tray, menu: TrayMenu) -> Self {
-        Self {
+        let mut runner = Self {
             tray: Arc::new(Mutex::new(tray)),
             menu: Arc::new(Mutex::new(menu)),
-        }
+            event_loop: None,
+        };
+
+        runner.event_loop = Some(runner.create_event_loop());
+        runner
+    }
+
+    /// Sends events to the tray from other threads.
+    pub fn proxy(&self) -> Option<EventLoopProxy<UserEvent>> {
+        self.event_loop
+            .as_ref()
+            .map(|event_loop| event_loop.create_proxy())
     }
 
-    pub fn run(&self, callback: impl Fn(TrayEvent, &mut TrayRef) + Send + 'static + Sync) {
-        let event_loop = self.create_event_loop();
+    pub fn run(&mut self, callback: impl Fn(TrayEvent, &mut TrayRef) + Send + 'static + Sync) {
+        let event_loop = self.event_loop.take().expect("Tray can only run once");
         let mut tray_ref = self.get_ref();
 
         event_loop
             .run(move |event, ael| {
                 ael.set_control_flow(ControlFlow::Wait);
 
-                if let Event::UserEvent(UserEvent::MenuEvent(e)) = event {
-                    let tray_event = TrayEvent::from(e.id.as_ref());
+                match event {
+                    Event::UserEvent(UserEvent::MenuEvent(e)) => {
+                        let tray_event = TrayEvent::from(e.id.as_ref());
 
-                    match tray_event {
-                        TrayEvent::Title => {}
-                        TrayEvent::Running => tray_ref.update_menu(),
-                        TrayEvent::Exit => ael.exit(),
-                    }
+                        match tray_event {
+                            TrayEvent::Title
+                            | TrayEvent::Warning
+                            | TrayEvent::Reload
+                            | TrayEvent::OpenLog => {}
+                            TrayEvent::Running => tray_ref.update_menu(),
+                            TrayEvent::Exit => ael.exit(),
+                        }
 
-                    callback(tray_event.clone(), &mut tray_ref);
+                        callback(tray_event.clone(), &mut tray_ref);
+                    }
+                    Event::UserEvent(UserEvent::Warning(warning)) => {
+                        tray_ref.set_warning(warning.as_deref());
+                    }
+                    Event::UserEvent(UserEvent::Running(running))
+                        if tray_ref.is_running() != running =>
+                    {
+                        tray_ref.update_menu();
+                    }
+                    Event::UserEvent(UserEvent::ConfigError(error)) => show_error(error),
+                    _ => {}
                 }
             })
             .unwrap();
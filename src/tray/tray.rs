@@ -35,6 +35,14 @@ impl Tray {
         self.tray.as_mut().unwrap().set_menu(menu.as_context_menu());
     }
 
+    pub fn set_tooltip(&mut self, tooltip: &str) {
+        self.tray
+            .as_mut()
+            .unwrap()
+            .set_tooltip(Some(tooltip))
+            .unwrap();
+    }
+
     pub fn set_icon(&mut self, icon: u16) {
         self.tray
             .as_mut()
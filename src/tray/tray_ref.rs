@@ -1,4 +1,7 @@
-use crate::tray::constants::{ICON_GREEN, ICON_RED, MENU_DISABLED, MENU_RUNNING, MENU_TITLE};
+use crate::tray::constants::{
+    APP_TOOLTIP, ICON_GREEN, ICON_RED, MENU_DISABLED, MENU_RUNNING, MENU_TITLE,
+    MENU_WARNING_POSITION,
+};
 use crate::tray::events::TrayEvent;
 use crate::tray::item_builder::{TrayItemBuilder, TrayMenuItemType};
 use crate::tray::menu::TrayMenu;
@@ -60,6 +63,34 @@ impl TrayRef {
         self.tray.lock().unwrap().set_icon(icon);
     }
 
+    /// Shows `warning` in the menu and tooltip, or clears it.
+    pub fn set_warning(&mut self, warning: Option<&str>) {
+        let id = MenuId::new(TrayEvent::Warning.as_str());
+        let mut menu = self.tray_menu.lock().unwrap();
+
+        menu.remove_item(&id);
+
+        if let Some(warning) = warning {
+            menu.insert_item(
+                TrayItemBuilder::new()
+                    .with_id(id)
+                    .with_title(warning)
+                    .with_enabled(false)
+                    .build(TrayMenuItemType::Normal),
+                MENU_WARNING_POSITION,
+            );
+        }
+
+        let tooltip = match warning {
+            Some(warning) => format!("{} - {}", APP_TOOLTIP, warning),
+            None => APP_TOOLTIP.to_string(),
+        };
+
+        let mut tray = self.tray.lock().unwrap();
+        tray.set_menu(&menu);
+        tray.set_tooltip(&tooltip);
+    }
+
     pub fn is_running(&self) -> bool {
         self.running.load(atomic::Ordering::Relaxed)
     }